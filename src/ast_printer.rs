@@ -0,0 +1,360 @@
+use crate::expr::{Expr, LiteralValue};
+use crate::pattern::Pattern;
+use crate::scanner::Span;
+use crate::stmt::{Function, Stmt};
+use crate::type_expr::TypeExpr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AstFormat {
+    // One S-expression per statement, as `Display` prints them.
+    SExpr,
+    // One node per line, children indented under their parent:
+    //
+    //   Let mut age @1:1
+    //     Type Int @1:14
+    //     Literal 23 @1:20
+    Tree,
+}
+
+pub fn print_ast(statements: &[Stmt], format: AstFormat) -> String {
+    let mut out = String::new();
+    match format {
+        AstFormat::SExpr => {
+            for statement in statements {
+                out.push_str(&format!("{}\n", statement));
+            }
+        }
+        AstFormat::Tree => {
+            let mut printer = TreePrinter { out, depth: 0 };
+            for statement in statements {
+                printer.statement(statement);
+            }
+            out = printer.out;
+        }
+    }
+    out
+}
+
+struct TreePrinter {
+    out: String,
+    depth: usize,
+}
+
+impl TreePrinter {
+    // Prints the node, then the children `print` prints, one level deeper.
+    fn node(&mut self, label: &str, span: Span, print: impl FnOnce(&mut Self)) {
+        self.out
+            .push_str(&format!("{}{} @{}\n", "  ".repeat(self.depth), label, span));
+        self.depth += 1;
+        print(self);
+        self.depth -= 1;
+    }
+
+    // The `///` comments of a declaration, which have no span of their own.
+    fn doc(&mut self, doc: &Option<String>) {
+        if let Some(doc) = doc {
+            self.out
+                .push_str(&format!("{}Doc {:?}\n", "  ".repeat(self.depth), doc));
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expression, span } => {
+                self.node("Expression", *span, |p| p.expression(expression))
+            }
+            Stmt::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                doc,
+                span,
+            } => {
+                let label = if *mutable {
+                    format!("Let mut {}", name.lexeme)
+                } else {
+                    format!("Let {}", name.lexeme)
+                };
+                self.node(&label, *span, |p| {
+                    p.doc(doc);
+                    if let Some(annotation) = type_annotation {
+                        p.type_expr(annotation);
+                    }
+                    if let Some(initializer) = initializer {
+                        p.expression(initializer);
+                    }
+                });
+            }
+            Stmt::Block { statements, span } => self.node("Block", *span, |p| {
+                for statement in statements {
+                    p.statement(statement);
+                }
+            }),
+            Stmt::Actor {
+                name,
+                fields,
+                methods,
+                doc,
+                span,
+            } => self.node(&format!("Actor {}", name.lexeme), *span, |p| {
+                p.doc(doc);
+                for field in fields {
+                    p.statement(field);
+                }
+                for method in methods {
+                    p.function(method);
+                }
+            }),
+            Stmt::Function(function) => self.function(function),
+            Stmt::Return { value, span, .. } => self.node("Return", *span, |p| {
+                if let Some(value) = value {
+                    p.expression(value);
+                }
+            }),
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        let label = format!("{} {}", function.kind, function.name.lexeme);
+        self.node(&label, function.span, |p| {
+            p.doc(&function.doc);
+            for param in &function.params {
+                p.node(
+                    &format!("Param {}", param.name.lexeme),
+                    param.name.span,
+                    |p| {
+                        if let Some(annotation) = &param.type_annotation {
+                            p.type_expr(annotation);
+                        }
+                    },
+                );
+            }
+            if let Some(return_type) = &function.return_type {
+                p.node("Returns", return_type.span(), |p| p.type_expr(return_type));
+            }
+            for statement in &function.body {
+                p.statement(statement);
+            }
+        });
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        let span = expr.span();
+        match expr {
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => self.node(&format!("Binary {}", operator.lexeme), span, |p| {
+                p.expression(left);
+                p.expression(right);
+            }),
+            Expr::Grouping { expression, .. } => {
+                self.node("Grouping", span, |p| p.expression(expression))
+            }
+            Expr::Literal { value, .. } => self.node(&literal_label(value), span, |_| ()),
+            Expr::Unary {
+                operator, right, ..
+            } => self.node(&format!("Unary {}", operator.lexeme), span, |p| {
+                p.expression(right)
+            }),
+            Expr::Variable { name, .. } => {
+                self.node(&format!("Variable {}", name.lexeme), span, |_| ())
+            }
+            Expr::Assign { name, value, .. } => {
+                self.node(&format!("Assign {}", name.lexeme), span, |p| {
+                    p.expression(value)
+                })
+            }
+            Expr::Call {
+                callee, arguments, ..
+            } => self.node("Call", span, |p| {
+                p.expression(callee);
+                for argument in arguments {
+                    p.expression(argument);
+                }
+            }),
+            Expr::Get { object, name, .. } => {
+                self.node(&format!("Get {}", name.lexeme), span, |p| {
+                    p.expression(object)
+                })
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => self.node(&format!("Set {}", name.lexeme), span, |p| {
+                p.expression(object);
+                p.expression(value);
+            }),
+            Expr::SelfRef { .. } => self.node("Self", span, |_| ()),
+            Expr::Pipe { left, right, .. } => self.node("Pipe", span, |p| {
+                p.expression(left);
+                p.expression(right);
+            }),
+            Expr::Tuple { elements, .. } => self.node("Tuple", span, |p| {
+                for element in elements {
+                    p.expression(element);
+                }
+            }),
+            Expr::Record { fields, .. } => self.node("Record", span, |p| {
+                for (name, value) in fields {
+                    p.node(&format!("Field {}", name.lexeme), name.span, |p| {
+                        p.expression(value)
+                    });
+                }
+            }),
+            Expr::Match { value, arms, .. } => self.node("Match", span, |p| {
+                p.expression(value);
+                for arm in arms {
+                    p.node("Arm", arm.span, |p| {
+                        p.pattern(&arm.pattern);
+                        if let Some(guard) = &arm.guard {
+                            p.node("Guard", guard.span(), |p| p.expression(guard));
+                        }
+                        p.statement(&arm.body);
+                    });
+                }
+            }),
+            Expr::Consume { name, .. } => {
+                self.node(&format!("Consume {}", name.lexeme), span, |_| ())
+            }
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        let span = pattern.span();
+        match pattern {
+            Pattern::Literal { value, .. } => self.node(&literal_label(value), span, |_| ()),
+            Pattern::Wildcard { .. } => self.node("Wildcard", span, |_| ()),
+            Pattern::Binding { name, .. } => {
+                self.node(&format!("Binding {}", name.lexeme), span, |_| ())
+            }
+            Pattern::Typed {
+                pattern, type_expr, ..
+            } => self.node("Typed", span, |p| {
+                p.pattern(pattern);
+                p.type_expr(type_expr);
+            }),
+            Pattern::Tuple { elements, .. } => self.node("TuplePattern", span, |p| {
+                for element in elements {
+                    p.pattern(element);
+                }
+            }),
+            Pattern::Record { fields, .. } => self.node("RecordPattern", span, |p| {
+                for (name, pattern) in fields {
+                    p.node(&format!("Field {}", name.lexeme), name.span, |p| {
+                        p.pattern(pattern)
+                    });
+                }
+            }),
+        }
+    }
+
+    fn type_expr(&mut self, type_expr: &TypeExpr) {
+        let span = type_expr.span();
+        match type_expr {
+            TypeExpr::Named { .. } => self.node(&format!("Type {}", type_expr), span, |_| ()),
+            TypeExpr::Union { types, .. } => self.node("Union", span, |p| {
+                for member in types {
+                    p.type_expr(member);
+                }
+            }),
+            TypeExpr::Tuple { types, .. } => self.node("TupleType", span, |p| {
+                for element in types {
+                    p.type_expr(element);
+                }
+            }),
+        }
+    }
+}
+
+// Strings are quoted, so `"1"` and `1` print differently.
+fn literal_label(value: &LiteralValue) -> String {
+    match value {
+        LiteralValue::StringValue(s) => format!("Literal {:?}", s),
+        other => format!("Literal {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cst::SyntaxTree;
+
+    fn print(source: &str, format: AstFormat) -> String {
+        let statements = SyntaxTree::parse(source).unwrap().ast().unwrap();
+        print_ast(&statements, format)
+    }
+
+    #[test]
+    fn prints_s_expressions() {
+        let source = "let mut age: Int = -1;\nprint(age |> String.from);";
+        assert_eq!(
+            print(source, AstFormat::SExpr),
+            "(let mut age: Int (- 1))\n(call print (|> age (. String from)))\n"
+        );
+
+        let source = "let m = \"a b\"; print(m, \"say \\\"hi\\\"\");";
+        assert_eq!(
+            print(source, AstFormat::SExpr),
+            "(let m \"a b\")\n(call print m \"say \\\"hi\\\"\")\n"
+        );
+    }
+
+    #[test]
+    fn prints_trees() {
+        let source = "actor Main {
+    let mut age: (Int | String) = 1;
+    be grow(by: Int iso) -> () { self.age = match consume by { 0 => (1,), x: Int if x > 1 => x }; }
+}
+/// Builds a record.
+fn f() { return {a: \"a\", b: nil}; }";
+        assert_eq!(
+            print(source, AstFormat::Tree),
+            "Actor Main @1:1
+  Let mut age @2:5
+    Union @2:18
+      Type Int @2:19
+      Type String @2:25
+    Literal 1 @2:35
+  be grow @3:5
+    Param by @3:13
+      Type Int iso @3:17
+    Returns @3:29
+      TupleType @3:29
+    Expression @3:34
+      Set age @3:34
+        Self @3:34
+        Match @3:45
+          Consume by @3:51
+          Arm @3:64
+            Literal 0 @3:64
+            Expression @3:69
+              Tuple @3:69
+                Literal 1 @3:70
+          Arm @3:75
+            Typed @3:75
+              Binding x @3:75
+              Type Int @3:78
+            Guard @3:85
+              Binary > @3:85
+                Variable x @3:85
+                Literal 1 @3:89
+            Expression @3:94
+              Variable x @3:94
+fn f @6:1
+  Doc \"Builds a record.\"
+  Return @6:10
+    Record @6:17
+      Field a @6:18
+        Literal \"a\" @6:21
+      Field b @6:26
+        Literal nil @6:29
+"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use std::fmt;
+
+// Reference capabilities, written after a type name: `String iso`.
+//
+//   iso   the only reference to a mutable value, can be sent
+//   trn   the only mutable reference, the others are read only
+//   ref   a mutable reference, there may be others
+//   val   an immutable value, can be sent
+//   box   read only, the value may be ref or val
+//   tag   only identifies a value, can be sent
+//
+// Only iso, val and tag values cross a behaviour boundary: an iso value has
+// no other reference left behind, a val one never changes, and a tag one
+// can't be read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Capability {
+    Iso,
+    Trn,
+    Ref,
+    Val,
+    Box,
+    Tag,
+}
+
+impl Capability {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "iso" => Some(Capability::Iso),
+            "trn" => Some(Capability::Trn),
+            "ref" => Some(Capability::Ref),
+            "val" => Some(Capability::Val),
+            "box" => Some(Capability::Box),
+            "tag" => Some(Capability::Tag),
+            _ => None,
+        }
+    }
+
+    pub fn is_sendable(self) -> bool {
+        matches!(self, Capability::Iso | Capability::Val | Capability::Tag)
+    }
+
+    // Whether a reference can be sent as it is, a moved trn one becomes val.
+    pub fn can_send(self, moved: bool) -> bool {
+        self.is_sendable() || (moved && self == Capability::Trn)
+    }
+
+    // The capability of a copy of a reference: copying an iso one leaves two
+    // references, so the copy may only identify the value.
+    pub fn alias(self) -> Self {
+        match self {
+            Capability::Iso => Capability::Tag,
+            Capability::Trn => Capability::Box,
+            other => other,
+        }
+    }
+
+    // The capabilities a reference can be used as. `moved` references have
+    // no other copy left, e.g. after `consume` or when just created.
+    pub fn usable_as(self, moved: bool) -> &'static [Capability] {
+        use Capability::*;
+        match (self, moved) {
+            (Iso, true) => &[Iso, Trn, Ref, Val, Box, Tag],
+            (Trn, true) => &[Trn, Ref, Val, Box, Tag],
+            (Iso, false) => &[Tag],
+            (Trn, false) => &[Trn, Box, Tag],
+            (Ref, _) => &[Ref, Box, Tag],
+            (Val, _) => &[Val, Box, Tag],
+            (Box, _) => &[Box, Tag],
+            (Tag, _) => &[Tag],
+        }
+    }
+
+    // The most a value of either capability can be used as, e.g. a binding
+    // holding either a ref or a val value may only read it.
+    pub fn join(self, other: Self) -> Self {
+        use Capability::*;
+        match (self, other) {
+            (a, b) if a == b => a,
+            (Tag, _) | (_, Tag) | (Iso, _) | (_, Iso) => Tag,
+            _ => Box,
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Capability::Iso => "iso",
+            Capability::Trn => "trn",
+            Capability::Ref => "ref",
+            Capability::Val => "val",
+            Capability::Box => "box",
+            Capability::Tag => "tag",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// `iso, val or tag`, for error notes.
+pub fn describe(capabilities: &[Capability]) -> String {
+    let names: Vec<String> = capabilities.iter().map(|c| c.to_string()).collect();
+    match names.split_last() {
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn copies_of_unique_references_lose_permissions() {
+        assert_eq!(Capability::Iso.alias(), Capability::Tag);
+        assert_eq!(Capability::Trn.alias(), Capability::Box);
+        assert_eq!(Capability::Ref.alias(), Capability::Ref);
+
+        assert!(Capability::Iso.usable_as(true).contains(&Capability::Val));
+        assert!(!Capability::Iso.usable_as(false).contains(&Capability::Val));
+        assert_eq!(Capability::Iso.usable_as(false), &[Capability::Tag]);
+        assert!(!Capability::Ref.usable_as(true).contains(&Capability::Val));
+        assert!(!Capability::Box.usable_as(false).contains(&Capability::Ref));
+    }
+
+    #[test]
+    fn joins_keep_what_both_allow() {
+        assert_eq!(Capability::Val.join(Capability::Val), Capability::Val);
+        assert_eq!(Capability::Ref.join(Capability::Val), Capability::Box);
+        assert_eq!(Capability::Iso.join(Capability::Val), Capability::Tag);
+        assert_eq!(
+            describe(Capability::Val.usable_as(false)),
+            "val, box or tag"
+        );
+    }
+}
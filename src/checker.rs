@@ -0,0 +1,2280 @@
+use crate::capability::{self, Capability};
+use crate::expr::{Expr, LiteralValue, MatchArm};
+use crate::interpreter::{NativeFunction, Value};
+use crate::pattern::Pattern;
+use crate::scanner::{Span, Token, TokenType};
+use crate::stdlib::{self, Module};
+use crate::stmt::{Function, FunctionKind, Stmt};
+use crate::type_expr::TypeExpr;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+// Overload resolution gives up on calls whose union arguments expand to more
+// combinations than this, leaving them to the runtime.
+const MAX_COMBINATIONS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type {
+    Int,
+    Float,
+    String,
+    Bool,
+    Nil,
+    // An actor, by the name of its class.
+    Actor(String),
+    ActorClass(String),
+    Module(String),
+    // The overloads of a function. `Fn` annotations have none and accept any
+    // function.
+    Function(Vec<Signature>),
+    Union(Vec<Type>),
+    Tuple(Vec<Type>),
+    // Record literals, fields in the order they were written.
+    Record(Vec<(String, Type)>),
+    // Unannotated parameters and whatever can't be told before running,
+    // compatible with every other type.
+    Unknown,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub kind: FunctionKind,
+    pub params: Vec<Type>,
+    pub returns: Type,
+    // As annotated, `None` where no type was written.
+    capabilities: Vec<Option<Capability>>,
+    returns_capability: Option<Capability>,
+    // Parameters with a type annotation, the more the more specific.
+    typed_params: usize,
+    // How the declaration reads, e.g. `f(by: Int)`, for error notes.
+    label: String,
+    // Natives are replaced, not overloaded, by a fn of the same name.
+    native: bool,
+}
+
+impl Signature {
+    // As `overload::same_signature`: the later of two such declarations
+    // replaces the earlier.
+    fn same_params(&self, other: &Signature) -> bool {
+        self.params == other.params && self.capabilities == other.capabilities
+    }
+}
+
+impl Type {
+    // Nested unions are flattened and duplicates dropped, a union of one type
+    // is that type.
+    pub fn union(types: Vec<Type>) -> Type {
+        let mut members: Vec<Type> = vec![];
+        for t in types {
+            let flat = match t {
+                Type::Union(inner) => inner,
+                Type::Unknown => return Type::Unknown,
+                other => vec![other],
+            };
+            for member in flat {
+                if !members.contains(&member) {
+                    members.push(member);
+                }
+            }
+        }
+        match members.len() {
+            0 => Type::Unknown,
+            1 => members.pop().unwrap(),
+            _ => Type::Union(members),
+        }
+    }
+
+    fn members(&self) -> Vec<Type> {
+        match self {
+            Type::Union(types) => types.clone(),
+            other => vec![other.clone()],
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        self.members()
+            .iter()
+            .all(|t| matches!(t, Type::Int | Type::Float))
+    }
+
+    pub fn is_assignable_to(&self, to: &Type) -> bool {
+        match (self, to) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Union(members), _) => members.iter().all(|m| m.is_assignable_to(to)),
+            (_, Type::Union(members)) => members.iter().any(|m| self.is_assignable_to(m)),
+            (Type::Function(_), Type::Function(_)) => true,
+            (Type::Tuple(a), Type::Tuple(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.is_assignable_to(b))
+            }
+            (Type::Record(a), Type::Record(b)) => {
+                a.len() == b.len()
+                    && b.iter().all(|(name, to)| {
+                        a.iter()
+                            .any(|(field, from)| field == name && from.is_assignable_to(to))
+                    })
+            }
+            (from, to) => from == to,
+        }
+    }
+
+    // What a variable declared as `self` holds right after `value` is
+    // assigned to it: unions narrow to the assigned type.
+    fn narrow(&self, value: &Type) -> Type {
+        match self {
+            Type::Union(_) if *value != Type::Unknown && value.is_assignable_to(self) => {
+                value.clone()
+            }
+            declared => declared.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "Int"),
+            Type::Float => write!(f, "Float"),
+            Type::String => write!(f, "String"),
+            Type::Bool => write!(f, "Bool"),
+            Type::Nil => write!(f, "Nil"),
+            Type::Actor(name) => write!(f, "{}", name),
+            Type::ActorClass(name) => write!(f, "actor {}", name),
+            Type::Module(name) => write!(f, "module {}", name),
+            Type::Function(_) => write!(f, "Fn"),
+            Type::Union(types) => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", types.join(" | "))
+            }
+            Type::Tuple(types) => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", types.join(", "))
+            }
+            Type::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, t)| format!("{}: {}", name, t))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+            Type::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeError {
+    pub message: String,
+    pub span: Span,
+    pub note: Option<String>,
+}
+
+impl TypeError {
+    fn new(span: Span, message: String) -> Self {
+        Self {
+            message,
+            span,
+            note: None,
+        }
+    }
+
+    fn with_note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        self
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Type error: {}", self.span, self.message)
+    }
+}
+
+#[derive(Clone)]
+struct Binding {
+    declared: Type,
+    // The declared type narrowed by the last assignment.
+    current: Type,
+    mutable: bool,
+    // `None` when it isn't known, nothing is checked then.
+    capability: Option<Capability>,
+    // Moved out by `consume` and not assigned since.
+    consumed: bool,
+}
+
+#[derive(Clone, Default)]
+struct ActorInfo {
+    // Type and mutability of every field.
+    fields: HashMap<String, (Type, bool)>,
+    capabilities: HashMap<String, Capability>,
+    methods: HashMap<String, Vec<Signature>>,
+}
+
+// The innermost function being checked.
+#[derive(Clone)]
+struct FunctionContext {
+    name: String,
+    returns: Option<Type>,
+    returns_capability: Option<Capability>,
+    // Scopes from this index on belong to the function, bindings in the ones
+    // before it may be reassigned whenever the function runs.
+    first_scope: usize,
+}
+
+// Checks a program before it runs. Types are inferred for `let`s without an
+// annotation, unannotated parameters are `_` and accept anything, and so do
+// the values computed from them: the checker only reports what is sure to go
+// wrong, the rest is left to the runtime.
+//
+// Bindings declared with a union type are narrowed by assignment:
+//
+//   let mut age: (Int | String) = 1;
+//   age + 1;         // fine, `age` holds an Int here
+//
+// and by the type patterns of `match` arms. Matches on a union that leave some
+// of its members uncovered are warned about, they fail at runtime if one of
+// those turns up.
+//
+// Reference capabilities (see `capability.rs`) are checked where a value is
+// bound to an annotated name or sent to a behaviour. A type written without
+// one is val, actors are tag; unannotated names aren't checked:
+//
+//   let s: String iso = "lok";
+//   let t = s;           // t is tag, a copy of an iso reference
+//   log.log(consume s);  // moves s, which can't be used any more
+#[derive(Clone)]
+pub struct Checker {
+    scopes: Vec<HashMap<String, Binding>>,
+    actors: HashMap<String, ActorInfo>,
+    modules: HashMap<String, Arc<Module>>,
+    function: Option<FunctionContext>,
+    // The actor whose method is being checked.
+    actor: Option<String>,
+    errors: Vec<TypeError>,
+    // Reported, but don't stop the program from running.
+    warnings: Vec<TypeError>,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+        let mut globals = HashMap::new();
+        let mut modules = HashMap::new();
+        for (name, value) in stdlib::globals() {
+            let declared = match value {
+                Value::NativeFunction(native) => Type::Function(vec![native_signature(&native)]),
+                Value::Module(module) => {
+                    modules.insert(name.to_string(), module);
+                    Type::Module(name.to_string())
+                }
+                _ => Type::Unknown,
+            };
+            globals.insert(
+                name.to_string(),
+                Binding {
+                    current: declared.clone(),
+                    declared,
+                    mutable: false,
+                    capability: Some(Capability::Val),
+                    consumed: false,
+                },
+            );
+        }
+        Self {
+            scopes: vec![globals],
+            actors: HashMap::new(),
+            modules,
+            function: None,
+            actor: None,
+            errors: vec![],
+            warnings: vec![],
+        }
+    }
+
+    // Declarations are kept between calls, so a REPL can check one line at
+    // a time.
+    pub fn check(&mut self, statements: &[Stmt]) -> Result<(), Vec<TypeError>> {
+        self.declare_items(statements);
+        for statement in statements {
+            self.statement(statement);
+        }
+
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // The type of `expr` in the scope of what was checked so far. It may
+    // mark bindings consumed like `check` does, tools check a clone.
+    pub fn type_of(&mut self, expr: &Expr) -> Result<Type, Vec<TypeError>> {
+        let found = self.expression(expr);
+        if self.errors.is_empty() {
+            Ok(found)
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+
+    // The warnings of every `check` since the last call.
+    pub fn take_warnings(&mut self) -> Vec<TypeError> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn error(&mut self, span: Span, message: String) {
+        self.errors.push(TypeError::new(span, message));
+    }
+
+    fn define(&mut self, name: &str, declared: Type, current: Type, mutable: bool) {
+        self.scopes.last_mut().unwrap().insert(
+            name.to_string(),
+            Binding {
+                declared,
+                current,
+                mutable,
+                capability: None,
+                consumed: false,
+            },
+        );
+    }
+
+    // Of a name just defined.
+    fn set_capability(&mut self, name: &str, capability: Option<Capability>) {
+        if let Some(binding) = self.scopes.last_mut().unwrap().get_mut(name) {
+            binding.capability = capability;
+        }
+    }
+
+    fn binding(&self, name: &str) -> Option<&Binding> {
+        self.scopes.iter().rev().find_map(|scope| scope.get(name))
+    }
+
+    // Actors and functions can be used anywhere in the block declaring them,
+    // e.g. by functions declared before them.
+    fn declare_items(&mut self, statements: &[Stmt]) {
+        for statement in statements {
+            if let Stmt::Actor { name, .. } = statement {
+                self.actors.entry(name.lexeme.clone()).or_default();
+                let class = Type::ActorClass(name.lexeme.clone());
+                self.define(&name.lexeme, class.clone(), class, false);
+            }
+        }
+
+        let mut functions: Vec<(String, Vec<Signature>)> = vec![];
+        for statement in statements {
+            match statement {
+                Stmt::Actor { name, methods, .. } => {
+                    let mut signatures: HashMap<String, Vec<Signature>> = HashMap::new();
+                    for method in methods {
+                        signatures
+                            .entry(method.name.lexeme.clone())
+                            .or_default()
+                            .push(self.signature(method));
+                    }
+                    self.actors.get_mut(&name.lexeme).unwrap().methods = signatures;
+                }
+                Stmt::Function(function) => {
+                    let signature = self.signature(function);
+                    match functions
+                        .iter_mut()
+                        .find(|(name, _)| *name == function.name.lexeme)
+                    {
+                        Some((_, overloads)) => overloads.push(signature),
+                        None => functions.push((function.name.lexeme.clone(), vec![signature])),
+                    }
+                }
+                _ => (),
+            }
+        }
+        // A fn joins the overloads declared before it in the same scope, e.g.
+        // in an earlier REPL entry, as it does when the program runs.
+        for (name, signatures) in functions {
+            let mut overloads = match self.scopes.last().unwrap().get(&name) {
+                Some(Binding {
+                    declared: Type::Function(overloads),
+                    ..
+                }) => overloads.iter().filter(|s| !s.native).cloned().collect(),
+                _ => vec![],
+            };
+            for signature in signatures {
+                overloads.retain(|overload| !overload.same_params(&signature));
+                overloads.push(signature);
+            }
+            let function = Type::Function(overloads);
+            self.define(&name, function.clone(), function, false);
+        }
+    }
+
+    // Unknown types are reported when the declaration itself is checked.
+    fn signature(&self, function: &Function) -> Signature {
+        let params = function
+            .params
+            .iter()
+            .map(|param| match &param.type_annotation {
+                Some(annotation) => self.resolve(annotation).unwrap_or(Type::Unknown),
+                None => Type::Unknown,
+            })
+            .collect();
+        let returns = match (&function.return_type, function.kind) {
+            (_, FunctionKind::Behaviour) => Type::Nil,
+            (Some(annotation), _) => self.resolve(annotation).unwrap_or(Type::Unknown),
+            (None, _) => Type::Unknown,
+        };
+        let capabilities = function
+            .params
+            .iter()
+            .map(|param| {
+                param
+                    .type_annotation
+                    .as_ref()
+                    .and_then(|annotation| self.annotation_capability(annotation))
+            })
+            .collect();
+        let returns_capability = match function.kind {
+            FunctionKind::Behaviour => None,
+            FunctionKind::Function => function
+                .return_type
+                .as_ref()
+                .and_then(|annotation| self.annotation_capability(annotation)),
+        };
+        let labels: Vec<String> = function.params.iter().map(|p| p.to_string()).collect();
+        Signature {
+            kind: function.kind,
+            params,
+            returns,
+            capabilities,
+            returns_capability,
+            typed_params: function
+                .params
+                .iter()
+                .filter(|param| param.type_annotation.is_some())
+                .count(),
+            label: format!("{}({})", function.name.lexeme, labels.join(", ")),
+            native: false,
+        }
+    }
+
+    fn resolve(&self, type_expr: &TypeExpr) -> Result<Type, TypeError> {
+        match type_expr {
+            TypeExpr::Named { name, span, .. } => match builtin_type(&name.lexeme) {
+                Some(t) => Ok(t),
+                None if self.actors.contains_key(&name.lexeme) => {
+                    Ok(Type::Actor(name.lexeme.clone()))
+                }
+                None => Err(TypeError::new(
+                    *span,
+                    format!("Unknown type '{}'", name.lexeme),
+                )),
+            },
+            TypeExpr::Union { types, .. } => {
+                let types = types
+                    .iter()
+                    .map(|t| self.resolve(t))
+                    .collect::<Result<Vec<Type>, TypeError>>()?;
+                Ok(Type::union(types))
+            }
+            TypeExpr::Tuple { types, .. } => {
+                let types = types
+                    .iter()
+                    .map(|t| self.resolve(t))
+                    .collect::<Result<Vec<Type>, TypeError>>()?;
+                Ok(Type::Tuple(types))
+            }
+        }
+    }
+
+    // The capability written after a type, or the default one for the type.
+    fn annotation_capability(&self, type_expr: &TypeExpr) -> Option<Capability> {
+        match type_expr {
+            TypeExpr::Named {
+                capability: Some(capability),
+                ..
+            } => Some(*capability),
+            TypeExpr::Named { .. } => default_capability(&self.resolve(type_expr).ok()?),
+            TypeExpr::Union { types, .. } | TypeExpr::Tuple { types, .. } => types
+                .iter()
+                .map(|t| self.annotation_capability(t))
+                .try_fold(Capability::Val, |joined, c| Some(joined.join(c?))),
+        }
+    }
+
+    fn resolve_reported(&mut self, type_expr: &TypeExpr) -> Type {
+        match self.resolve(type_expr) {
+            Ok(t) => t,
+            Err(err) => {
+                self.errors.push(err);
+                Type::Unknown
+            }
+        }
+    }
+
+    // Returns the type of the value the statement evaluates to.
+    fn statement(&mut self, statement: &Stmt) -> Type {
+        match statement {
+            Stmt::Expression { expression, .. } => self.expression(expression),
+            Stmt::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                let value = initializer.as_ref().map(|expr| self.expression(expr));
+                let declared = match (type_annotation, &value) {
+                    (Some(annotation), _) => self.resolve_reported(annotation),
+                    // `let mut x = nil;` is usually assigned a value later.
+                    (None, Some(Type::Nil)) | (None, None) => Type::Unknown,
+                    (None, Some(value)) => value.clone(),
+                };
+                let current = match (value, initializer) {
+                    (Some(value), Some(initializer)) => {
+                        self.expect(&value, &declared, initializer.span());
+                        declared.narrow(&value)
+                    }
+                    _ => declared.clone(),
+                };
+                let capability = self.binding_capability(type_annotation, initializer, &declared);
+                if let (Some(_), Some(initializer)) = (type_annotation, initializer) {
+                    self.expect_capability(initializer, capability);
+                }
+                self.define(&name.lexeme, declared, current, *mutable);
+                self.set_capability(&name.lexeme, capability);
+                Type::Nil
+            }
+            Stmt::Block { statements, .. } => {
+                self.scopes.push(HashMap::new());
+                let value = self.block(statements);
+                self.scopes.pop();
+                value
+            }
+            Stmt::Actor {
+                name,
+                fields,
+                methods,
+                ..
+            } => {
+                self.actor_declaration(name, fields, methods);
+                Type::Nil
+            }
+            Stmt::Function(function) => {
+                self.function(function);
+                Type::Nil
+            }
+            Stmt::Return { keyword, value, .. } => {
+                let value_type = match value {
+                    Some(expr) => self.expression(expr),
+                    None => Type::Nil,
+                };
+                let span = value.as_ref().map_or(keyword.span, |expr| expr.span());
+                if let (Some(value), Some(function)) = (value, &self.function) {
+                    let expected = function.returns_capability;
+                    self.expect_capability(value, expected);
+                }
+                if let Some(FunctionContext {
+                    name,
+                    returns: Some(returns),
+                    ..
+                }) = &self.function
+                {
+                    if !value_type.is_assignable_to(returns) {
+                        let message = format!(
+                            "Expected '{}' to return {}, found {}",
+                            name, returns, value_type
+                        );
+                        self.error(span, message);
+                    }
+                }
+                // Nothing after a `return` runs.
+                Type::Unknown
+            }
+        }
+    }
+
+    // The statements of a block or function body, in the current scope.
+    fn block(&mut self, statements: &[Stmt]) -> Type {
+        self.declare_items(statements);
+        let mut value = Type::Nil;
+        for statement in statements {
+            value = self.statement(statement);
+        }
+        value
+    }
+
+    fn actor_declaration(&mut self, name: &Token, fields: &[Stmt], methods: &[Function]) {
+        // Field initializers run outside of the actor, when it is spawned.
+        let mut field_types = HashMap::new();
+        let mut field_capabilities = HashMap::new();
+        for field in fields {
+            if let Stmt::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                ..
+            } = field
+            {
+                let value = initializer.as_ref().map(|expr| self.expression(expr));
+                let declared = match (type_annotation, &value) {
+                    (Some(annotation), _) => self.resolve_reported(annotation),
+                    (None, Some(Type::Nil)) | (None, None) => Type::Unknown,
+                    (None, Some(value)) => value.clone(),
+                };
+                if let (Some(value), Some(initializer)) = (value, initializer) {
+                    self.expect(&value, &declared, initializer.span());
+                }
+                let capability = self.binding_capability(type_annotation, initializer, &declared);
+                if let Some(capability) = capability {
+                    field_capabilities.insert(name.lexeme.clone(), capability);
+                }
+                field_types.insert(name.lexeme.clone(), (declared, *mutable));
+            }
+        }
+        let info = self.actors.get_mut(&name.lexeme).unwrap();
+        info.fields = field_types;
+        info.capabilities = field_capabilities;
+
+        let previous = self.actor.replace(name.lexeme.clone());
+        for method in methods {
+            self.function(method);
+        }
+        self.actor = previous;
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.scopes.push(HashMap::new());
+        for param in &function.params {
+            let declared = match &param.type_annotation {
+                Some(annotation) => self.resolve_reported(annotation),
+                None => Type::Unknown,
+            };
+            // Untyped parameters are val, see `argument_capabilities`.
+            let capability = match &param.type_annotation {
+                Some(annotation) => self.annotation_capability(annotation),
+                None => Some(Capability::Val),
+            };
+            self.define(&param.name.lexeme, declared.clone(), declared, false);
+            self.set_capability(&param.name.lexeme, capability);
+
+            match (function.kind, capability, &param.type_annotation) {
+                (FunctionKind::Behaviour, Some(capability), Some(annotation))
+                    if !capability.is_sendable() =>
+                {
+                    let err = TypeError::new(
+                        annotation.span(),
+                        format!(
+                            "Behaviour parameter '{}' is {}, which cannot be sent to another actor",
+                            param.name.lexeme, capability
+                        ),
+                    )
+                    .with_note("only iso, val and tag values can cross a behaviour boundary");
+                    self.errors.push(err);
+                }
+                _ => (),
+            }
+        }
+        let returns = function
+            .return_type
+            .as_ref()
+            .map(|annotation| self.resolve_reported(annotation));
+        let returns_capability = match function.kind {
+            FunctionKind::Behaviour => None,
+            FunctionKind::Function => function
+                .return_type
+                .as_ref()
+                .and_then(|annotation| self.annotation_capability(annotation)),
+        };
+
+        let context = FunctionContext {
+            name: function.name.lexeme.clone(),
+            returns: returns.clone(),
+            returns_capability,
+            first_scope: self.scopes.len() - 1,
+        };
+        let enclosing = self.function.replace(context);
+        let value = self.block(&function.body);
+        if let Some(Stmt::Expression { expression, .. }) = function.body.last() {
+            self.expect_capability(expression, returns_capability);
+        }
+        self.function = enclosing;
+        self.scopes.pop();
+
+        // Without a `return` the function evaluates to its last statement.
+        if let Some(returns) = returns {
+            if !value.is_assignable_to(&returns) {
+                let span = function
+                    .body
+                    .last()
+                    .map_or(function.name.span, |s| s.span());
+                self.error(
+                    span,
+                    format!(
+                        "Expected '{}' to return {}, found {}",
+                        function.name.lexeme, returns, value
+                    ),
+                );
+            }
+        }
+    }
+
+    // Annotated names take the capability written, the others that of their
+    // initial value: a copy keeps the capability of what it copies, a value
+    // just created gets the default one for its type.
+    fn binding_capability(
+        &self,
+        annotation: &Option<TypeExpr>,
+        initializer: &Option<Expr>,
+        declared: &Type,
+    ) -> Option<Capability> {
+        if let Some(annotation) = annotation {
+            return self.annotation_capability(annotation);
+        }
+        match initializer
+            .as_ref()
+            .map(|expr| (expr, self.reference(expr)))
+        {
+            Some((_, Some((capability, false))))
+            | Some((Expr::Consume { .. }, Some((capability, _)))) => Some(capability),
+            _ => default_capability(declared),
+        }
+    }
+
+    // The capability of the reference `expr` evaluates to and whether it was
+    // moved, i.e. no other reference to the value is left. `None` when it
+    // isn't known.
+    fn reference(&self, expr: &Expr) -> Option<(Capability, bool)> {
+        match expr {
+            Expr::Literal {
+                value: LiteralValue::StringValue(_),
+                ..
+            } => Some((Capability::Iso, true)),
+            Expr::Literal { .. } => Some((Capability::Val, false)),
+            Expr::Grouping { expression, .. } => self.reference(expression),
+            Expr::Variable { name, .. } => self
+                .binding(&name.lexeme)?
+                .capability
+                .map(|capability| (capability.alias(), false)),
+            Expr::Consume { name, .. } => self
+                .binding(&name.lexeme)?
+                .capability
+                .map(|capability| (capability, true)),
+            Expr::SelfRef { .. } => Some((Capability::Tag, false)),
+            Expr::Get { object, name, .. } if matches!(object.as_ref(), Expr::SelfRef { .. }) => {
+                let capabilities = &self.actors.get(self.actor.as_ref()?)?.capabilities;
+                capabilities
+                    .get(&name.lexeme)
+                    .map(|capability| (capability.alias(), false))
+            }
+            Expr::Call { callee, .. } => {
+                let (overloads, _) = self.callee_signatures(callee)?;
+                match overloads.as_slice() {
+                    [signature] => signature.returns_capability.map(|c| (c, true)),
+                    _ => None,
+                }
+            }
+            // Nothing else refers to a value just built from sendable ones.
+            Expr::Tuple { elements, .. } => self.built_reference(elements.iter()),
+            Expr::Record { fields, .. } => self.built_reference(fields.iter().map(|(_, e)| e)),
+            _ => None,
+        }
+    }
+
+    fn built_reference<'a>(
+        &self,
+        elements: impl Iterator<Item = &'a Expr>,
+    ) -> Option<(Capability, bool)> {
+        let mut sendable = true;
+        for element in elements {
+            let (capability, moved) = self.reference(element)?;
+            sendable &= capability.can_send(moved);
+        }
+        if sendable {
+            Some((Capability::Iso, true))
+        } else {
+            Some((Capability::Ref, true))
+        }
+    }
+
+    // The overloads a call may run without reporting anything, and whether
+    // they are behaviours. Actor classes are called to spawn an actor.
+    fn callee_signatures(&self, callee: &Expr) -> Option<(Vec<Signature>, bool)> {
+        let actor = match callee {
+            Expr::Variable { name, .. } => {
+                return match self.lookup(&name.lexeme)? {
+                    Type::Function(overloads) => Some((overloads, false)),
+                    Type::ActorClass(_) => Some((
+                        vec![Signature {
+                            kind: FunctionKind::Function,
+                            params: vec![],
+                            returns: Type::Unknown,
+                            capabilities: vec![],
+                            returns_capability: Some(Capability::Tag),
+                            typed_params: 0,
+                            label: String::new(),
+                            native: false,
+                        }],
+                        false,
+                    )),
+                    _ => None,
+                }
+            }
+            Expr::Get { object, .. } => self.actor_of(object)?,
+            _ => return None,
+        };
+        let Expr::Get { name, .. } = callee else {
+            return None;
+        };
+        let overloads = self.actors.get(&actor)?.methods.get(&name.lexeme)?.clone();
+        let behaviour = overloads[0].kind == FunctionKind::Behaviour;
+        Some((overloads, behaviour))
+    }
+
+    // The actor `expr` refers to, when that's known without checking it
+    // again: `self`, a name holding an actor, or one just spawned.
+    fn actor_of(&self, expr: &Expr) -> Option<String> {
+        match expr {
+            Expr::SelfRef { .. } => self.actor.clone(),
+            Expr::Grouping { expression, .. } => self.actor_of(expression),
+            Expr::Variable { name, .. } => match self.lookup(&name.lexeme)? {
+                Type::Actor(actor) => Some(actor),
+                _ => None,
+            },
+            Expr::Call { callee, .. } => match callee.as_ref() {
+                Expr::Variable { name, .. } => match self.lookup(&name.lexeme)? {
+                    Type::ActorClass(actor) => Some(actor),
+                    _ => None,
+                },
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // Arguments are bound to the parameters of the overload. Parameters
+    // without a type are val, so their arguments must be usable as val and,
+    // sent to a behaviour, sendable as they are. Actors are always tag.
+    fn argument_capabilities(&mut self, callee: &Expr, arguments: &[&Expr], types: &[Type]) {
+        let Some((overloads, behaviour)) = self.callee_signatures(callee) else {
+            return;
+        };
+        let candidates: Vec<&Signature> = overloads
+            .iter()
+            .filter(|signature| signature.capabilities.len() == arguments.len())
+            .collect();
+        for (index, argument) in arguments.iter().enumerate() {
+            let capabilities: Vec<Option<Capability>> =
+                candidates.iter().map(|s| s.capabilities[index]).collect();
+            match capabilities.as_slice() {
+                [Some(capability)] => self.expect_capability(argument, Some(*capability)),
+                _ if capabilities.iter().all(|c| c.is_none()) => {
+                    if matches!(types[index], Type::Actor(_) | Type::ActorClass(_)) {
+                        continue;
+                    }
+                    match self.reference(argument) {
+                        Some((capability, moved)) if behaviour && !capability.can_send(moved) => {
+                            let err = TypeError::new(
+                                argument.span(),
+                                format!(
+                                    "Cannot send a {} value to behaviour '{}'",
+                                    capability,
+                                    callee_name(callee)
+                                ),
+                            )
+                            .with_note(
+                                "only iso, val and tag values can cross a behaviour boundary",
+                            );
+                            self.errors.push(err);
+                        }
+                        _ => self.expect_capability(argument, Some(Capability::Val)),
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn expect_capability(&mut self, value: &Expr, expected: Option<Capability>) {
+        let (Some(expected), Some((found, moved))) = (expected, self.reference(value)) else {
+            return;
+        };
+        let usable = found.usable_as(moved);
+        if usable.contains(&expected) {
+            return;
+        }
+
+        let copied = match value {
+            Expr::Variable { name, .. } => self
+                .binding(&name.lexeme)
+                .and_then(|binding| binding.capability)
+                .filter(|c| matches!(c, Capability::Iso | Capability::Trn))
+                .map(|c| (name, c)),
+            _ => None,
+        };
+        let err = match copied {
+            Some((name, capability)) => TypeError::new(
+                value.span(),
+                format!("Cannot copy the {} reference '{}'", capability, name.lexeme),
+            )
+            .with_note(&match capability {
+                Capability::Iso => format!(
+                    "an iso value has only one reference, write 'consume {}' to move it instead",
+                    name.lexeme
+                ),
+                _ => format!(
+                    "copies of a trn reference are box, write 'consume {}' to move it instead",
+                    name.lexeme
+                ),
+            }),
+            None => TypeError::new(
+                value.span(),
+                format!(
+                    "Capability mismatch: expected {}, found {}",
+                    expected, found
+                ),
+            )
+            .with_note(&format!(
+                "a {} value can only be used as {}",
+                found,
+                capability::describe(usable)
+            )),
+        };
+        self.errors.push(err);
+    }
+
+    fn expect(&mut self, found: &Type, expected: &Type, span: Span) {
+        if !found.is_assignable_to(expected) {
+            self.error(
+                span,
+                format!("Mismatched types: expected {}, found {}", expected, found),
+            );
+        }
+    }
+
+    // Reads see the narrowed type, except for bindings declared outside of
+    // the current function, which may have been reassigned since.
+    fn lookup(&self, name: &str) -> Option<Type> {
+        let first_scope = self.function.as_ref().map_or(0, |f| f.first_scope);
+        self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| {
+                scope.get(name).map(|binding| {
+                    if index >= first_scope {
+                        binding.current.clone()
+                    } else {
+                        binding.declared.clone()
+                    }
+                })
+            })
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Type {
+        match expr {
+            Expr::Literal { value, .. } => literal_type(value),
+            Expr::Grouping { expression, .. } => self.expression(expression),
+            Expr::Unary {
+                operator, right, ..
+            } => {
+                let right = self.expression(right);
+                match operator.token_type {
+                    TokenType::Minus if right == Type::Unknown || right.is_numeric() => right,
+                    TokenType::Minus => {
+                        self.error(
+                            operator.span,
+                            format!("Operand of '-' must be a number, found {}", right),
+                        );
+                        Type::Unknown
+                    }
+                    _ => Type::Bool,
+                }
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.expression(left);
+                let right = self.expression(right);
+                self.binary(&left, operator, &right)
+            }
+            Expr::Variable { name, .. } => self.variable(name),
+            Expr::Consume { keyword, name, .. } => {
+                let t = self.variable(name);
+                self.consume(keyword, name);
+                t
+            }
+            Expr::Assign { name, value, .. } => {
+                let value_type = self.expression(value);
+                self.assign(name, &value_type, value.span());
+                let capability = self.binding(&name.lexeme).and_then(|b| b.capability);
+                self.expect_capability(value, capability);
+                value_type
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+                ..
+            } => {
+                let types: Vec<Type> = arguments.iter().map(|arg| self.expression(arg)).collect();
+                let result = self.call(callee, paren, types.clone(), expr.span());
+                let arguments: Vec<&Expr> = arguments.iter().collect();
+                self.argument_capabilities(callee, &arguments, &types);
+                result
+            }
+            Expr::Pipe {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let value = self.expression(left);
+                match right.as_ref() {
+                    Expr::Call {
+                        callee,
+                        paren,
+                        arguments,
+                        ..
+                    } => {
+                        let mut values = vec![value];
+                        values.extend(arguments.iter().map(|arg| self.expression(arg)));
+                        let result = self.call(callee, paren, values.clone(), expr.span());
+                        let mut arguments: Vec<&Expr> = arguments.iter().collect();
+                        arguments.insert(0, left);
+                        self.argument_capabilities(callee, &arguments, &values);
+                        result
+                    }
+                    other => {
+                        let result = self.call(other, operator, vec![value.clone()], expr.span());
+                        self.argument_capabilities(other, &[left], &[value]);
+                        result
+                    }
+                }
+            }
+            Expr::Get { object, name, .. } => match self.expression(object) {
+                Type::Module(module) => match self.module_function(&module, name) {
+                    Some(function) => Type::Function(vec![function]),
+                    None => Type::Unknown,
+                },
+                Type::Record(fields) => match fields.into_iter().find(|(f, _)| *f == name.lexeme) {
+                    Some((_, field)) => field,
+                    None => {
+                        self.error(name.span, format!("Record has no field '{}'", name.lexeme));
+                        Type::Unknown
+                    }
+                },
+                object => match self.field(&object, name) {
+                    Some((field, _)) => field,
+                    None => Type::Unknown,
+                },
+            },
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                let object = self.expression(object);
+                let value_type = self.expression(value);
+                if let Some((field, mutable)) = self.field(&object, name) {
+                    if !mutable {
+                        self.errors.push(
+                            TypeError::new(
+                                name.span,
+                                format!("Cannot assign twice to immutable field '{}'", name.lexeme),
+                            )
+                            .with_note(&format!(
+                                "declare it with 'let mut {}' to make it mutable",
+                                name.lexeme
+                            )),
+                        );
+                    }
+                    self.expect(&value_type, &field, value.span());
+                    let capability = self.actor.as_ref().and_then(|actor| {
+                        self.actors[actor].capabilities.get(&name.lexeme).copied()
+                    });
+                    self.expect_capability(value, capability);
+                }
+                value_type
+            }
+            Expr::SelfRef { keyword, .. } => match &self.actor {
+                Some(actor) => Type::Actor(actor.clone()),
+                None => {
+                    self.error(
+                        keyword.span,
+                        "Cannot use 'self' outside of an actor".to_string(),
+                    );
+                    Type::Unknown
+                }
+            },
+            Expr::Tuple { elements, .. } => {
+                Type::Tuple(elements.iter().map(|e| self.expression(e)).collect())
+            }
+            Expr::Record { fields, .. } => Type::Record(
+                fields
+                    .iter()
+                    .map(|(name, value)| (name.lexeme.clone(), self.expression(value)))
+                    .collect(),
+            ),
+            Expr::Match { value, arms, .. } => {
+                let value_type = self.expression(value);
+                self.match_arms(&value_type, arms, expr.span())
+            }
+        }
+    }
+
+    // Evaluates to any of the arm values.
+    fn match_arms(&mut self, value: &Type, arms: &[MatchArm], span: Span) -> Type {
+        let mut results = vec![];
+        let mut uncovered = value.members();
+        let mut booleans = vec![];
+        for arm in arms {
+            self.scopes.push(HashMap::new());
+            self.pattern(&arm.pattern, value);
+            if let Some(guard) = &arm.guard {
+                self.expression(guard);
+            }
+            results.push(self.statement(&arm.body));
+            self.scopes.pop();
+
+            // A guarded arm may not run, whatever its pattern.
+            if arm.guard.is_some() {
+                continue;
+            }
+            uncovered.retain(|member| !self.covers(&arm.pattern, member));
+            if let Pattern::Literal { value, .. } = &arm.pattern {
+                booleans.push(value.clone());
+            }
+        }
+        if booleans.contains(&LiteralValue::True) && booleans.contains(&LiteralValue::False) {
+            uncovered.retain(|member| *member != Type::Bool);
+        }
+
+        if matches!(value, Type::Union(_)) && !uncovered.is_empty() {
+            let warning = TypeError::new(
+                span,
+                format!(
+                    "Non-exhaustive match, {} not covered",
+                    Type::union(uncovered)
+                ),
+            )
+            .with_note("add an arm for the missing types, or a '_' arm");
+            self.warnings.push(warning);
+        }
+        Type::union(results)
+    }
+
+    // Defines the names `pattern` binds, narrowed to the values it matches.
+    fn pattern(&mut self, pattern: &Pattern, value: &Type) {
+        let never_matches = |checker: &mut Checker| {
+            checker.error(
+                pattern.span(),
+                format!("Pattern '{}' can never match {}", pattern, value),
+            )
+        };
+        match pattern {
+            Pattern::Literal { value: literal, .. } => {
+                if !literal_type(literal).is_assignable_to(value) {
+                    never_matches(self);
+                }
+            }
+            Pattern::Wildcard { .. } => (),
+            Pattern::Binding { name, .. } => {
+                self.define(&name.lexeme, value.clone(), value.clone(), false)
+            }
+            Pattern::Typed {
+                pattern: inner,
+                type_expr,
+                ..
+            } => {
+                let expected = self.resolve_reported(type_expr);
+                let members: Vec<Type> = value
+                    .members()
+                    .into_iter()
+                    .filter(|member| member.is_assignable_to(&expected))
+                    .collect();
+                let narrowed = if *value == Type::Unknown {
+                    expected
+                } else if members.is_empty() {
+                    never_matches(self);
+                    expected
+                } else {
+                    Type::union(members)
+                };
+                self.pattern(inner, &narrowed);
+            }
+            Pattern::Tuple { elements, .. } => {
+                let tuples: Vec<Vec<Type>> = value
+                    .members()
+                    .into_iter()
+                    .filter_map(|member| match member {
+                        Type::Tuple(types) if types.len() == elements.len() => Some(types),
+                        Type::Unknown => Some(vec![Type::Unknown; elements.len()]),
+                        _ => None,
+                    })
+                    .collect();
+                if tuples.is_empty() {
+                    never_matches(self);
+                }
+                for (index, element) in elements.iter().enumerate() {
+                    let types = tuples.iter().map(|t| t[index].clone()).collect();
+                    self.pattern(element, &Type::union(types));
+                }
+            }
+            Pattern::Record { fields, .. } => {
+                let records: Vec<Vec<(String, Type)>> = value
+                    .members()
+                    .into_iter()
+                    .filter_map(|member| match member {
+                        Type::Record(record) => Some(record),
+                        Type::Unknown => Some(vec![]),
+                        _ => None,
+                    })
+                    .collect();
+                if records.is_empty() {
+                    never_matches(self);
+                }
+                for (name, field) in fields {
+                    let types = records
+                        .iter()
+                        .map(
+                            |record| match record.iter().find(|(f, _)| *f == name.lexeme) {
+                                Some((_, t)) => t.clone(),
+                                None => Type::Unknown,
+                            },
+                        )
+                        .collect();
+                    self.pattern(field, &Type::union(types));
+                }
+            }
+        }
+    }
+
+    // Whether every value of `value` matches `pattern`. Unknown types in the
+    // pattern were reported when the arm was checked.
+    fn covers(&self, pattern: &Pattern, value: &Type) -> bool {
+        value
+            .members()
+            .iter()
+            .all(|member| match (pattern, member) {
+                (Pattern::Wildcard { .. } | Pattern::Binding { .. }, _) => true,
+                (Pattern::Literal { value, .. }, member) => {
+                    *value == LiteralValue::Nil && *member == Type::Nil
+                }
+                (
+                    Pattern::Typed {
+                        pattern, type_expr, ..
+                    },
+                    member,
+                ) => {
+                    let expected = self.resolve(type_expr).unwrap_or(Type::Unknown);
+                    member.is_assignable_to(&expected) && self.covers(pattern, member)
+                }
+                (Pattern::Tuple { elements, .. }, Type::Tuple(types)) => {
+                    elements.len() == types.len()
+                        && elements.iter().zip(types).all(|(p, t)| self.covers(p, t))
+                }
+                (Pattern::Record { fields, .. }, Type::Record(record)) => {
+                    fields.iter().all(|(name, pattern)| {
+                        record
+                            .iter()
+                            .any(|(field, t)| *field == name.lexeme && self.covers(pattern, t))
+                    })
+                }
+                _ => false,
+            })
+    }
+
+    fn variable(&mut self, name: &Token) -> Type {
+        let consumed = self.binding(&name.lexeme).is_some_and(|b| b.consumed);
+        if consumed {
+            let err = TypeError::new(
+                name.span,
+                format!("Cannot use '{}' after it was consumed", name.lexeme),
+            )
+            .with_note(&format!(
+                "assign '{}' a new value before using it again",
+                name.lexeme
+            ));
+            self.errors.push(err);
+        }
+        match self.lookup(&name.lexeme) {
+            Some(t) => t,
+            None => {
+                self.error(name.span, format!("Undefined variable '{}'", name.lexeme));
+                Type::Unknown
+            }
+        }
+    }
+
+    // Only locals of the current function can be consumed, the others may be
+    // read again whenever it runs.
+    fn consume(&mut self, keyword: &Token, name: &Token) {
+        let first_scope = self.function.as_ref().map_or(0, |f| f.first_scope);
+        let found = self
+            .scopes
+            .iter_mut()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| scope.get_mut(&name.lexeme).map(|b| (index, b)));
+        match found {
+            Some((index, binding)) if index >= first_scope => {
+                binding.consumed = true;
+            }
+            Some(_) => {
+                let err = TypeError::new(
+                    keyword.span.to(name.span),
+                    format!(
+                        "Cannot consume '{}', it is declared outside of this function",
+                        name.lexeme
+                    ),
+                );
+                self.errors.push(err);
+            }
+            None => (),
+        }
+    }
+
+    fn assign(&mut self, name: &Token, value: &Type, span: Span) {
+        let first_scope = self.function.as_ref().map_or(0, |f| f.first_scope);
+        let found = self
+            .scopes
+            .iter_mut()
+            .enumerate()
+            .rev()
+            .find_map(|(index, scope)| scope.get_mut(&name.lexeme).map(|b| (index, b)));
+        let (index, binding) = match found {
+            Some(found) => found,
+            None => {
+                self.error(name.span, format!("Undefined variable '{}'", name.lexeme));
+                return;
+            }
+        };
+
+        if !binding.mutable {
+            let err = TypeError::new(
+                name.span,
+                format!(
+                    "Cannot assign twice to immutable variable '{}'",
+                    name.lexeme
+                ),
+            )
+            .with_note(&format!(
+                "declare it with 'let mut {}' to make it mutable",
+                name.lexeme
+            ));
+            self.errors.push(err);
+            return;
+        }
+        binding.consumed = false;
+        let declared = binding.declared.clone();
+        // An assignment from inside a function may happen at any time, so the
+        // binding can't be narrowed for the code outside of it any more.
+        binding.current = if index >= first_scope {
+            declared.narrow(value)
+        } else {
+            declared.clone()
+        };
+        self.expect(value, &declared, span);
+    }
+
+    // The type of field `name` and whether it is mutable. Fields are private
+    // to their actor.
+    fn field(&mut self, object: &Type, name: &Token) -> Option<(Type, bool)> {
+        let actor = match object {
+            Type::Actor(actor) => actor,
+            Type::Unknown => return None,
+            other => {
+                self.error(
+                    name.span,
+                    format!("Only actors have fields, found {}", other),
+                );
+                return None;
+            }
+        };
+        if self.actor.as_ref() != Some(actor) {
+            let err = TypeError::new(
+                name.span,
+                format!(
+                    "Cannot access field '{}' of another actor '{}'",
+                    name.lexeme, actor
+                ),
+            )
+            .with_note("fields are private to their actor, send it a behaviour instead");
+            self.errors.push(err);
+            return None;
+        }
+
+        let info = &self.actors[actor];
+        match info.fields.get(&name.lexeme) {
+            Some(field) => Some(field.clone()),
+            None if info.methods.contains_key(&name.lexeme) => {
+                self.error(
+                    name.span,
+                    format!("Method '{}' must be called", name.lexeme),
+                );
+                None
+            }
+            None => {
+                let message = format!("Actor '{}' has no field '{}'", actor, name.lexeme);
+                self.error(name.span, message);
+                None
+            }
+        }
+    }
+
+    fn call(&mut self, callee: &Expr, paren: &Token, arguments: Vec<Type>, span: Span) -> Type {
+        if let Expr::Get { object, name, .. } = callee {
+            let object = self.expression(object);
+            return self.method_call(&object, name, &arguments);
+        }
+
+        match self.expression(callee) {
+            Type::Function(overloads) if overloads.is_empty() => Type::Unknown,
+            Type::Function(overloads) => {
+                let name = callee_name(callee);
+                self.resolve_call(&name, &overloads, &arguments, span)
+            }
+            Type::ActorClass(name) => {
+                if !arguments.is_empty() {
+                    self.error(
+                        paren.span,
+                        format!("Expected 0 arguments but got {}", arguments.len()),
+                    );
+                }
+                Type::Actor(name)
+            }
+            Type::Unknown => Type::Unknown,
+            other => {
+                self.error(
+                    paren.span,
+                    format!("Can only call functions and actors, found {}", other),
+                );
+                Type::Unknown
+            }
+        }
+    }
+
+    fn module_function(&mut self, module: &str, name: &Token) -> Option<Signature> {
+        match self.modules[module].function(&name.lexeme) {
+            Some(native) => Some(native_signature(native)),
+            None => {
+                let message = format!("Module '{}' has no function '{}'", module, name.lexeme);
+                self.error(name.span, message);
+                None
+            }
+        }
+    }
+
+    fn method_call(&mut self, object: &Type, name: &Token, arguments: &[Type]) -> Type {
+        let actor = match object {
+            Type::Module(module) => {
+                return match self.module_function(module, name) {
+                    Some(function) => {
+                        let path = format!("{}.{}", module, name.lexeme);
+                        self.resolve_call(&path, &[function], arguments, name.span)
+                    }
+                    None => Type::Unknown,
+                };
+            }
+            Type::Actor(actor) => actor,
+            Type::Unknown => return Type::Unknown,
+            other => {
+                self.error(
+                    name.span,
+                    format!("Only actors have methods, found {}", other),
+                );
+                return Type::Unknown;
+            }
+        };
+        let overloads = match self.actors[actor].methods.get(&name.lexeme) {
+            Some(overloads) => overloads.clone(),
+            None => {
+                let message = format!("Actor '{}' has no method '{}'", actor, name.lexeme);
+                self.error(name.span, message);
+                return Type::Unknown;
+            }
+        };
+
+        if overloads[0].kind == FunctionKind::Function && self.actor.as_ref() != Some(actor) {
+            let err = TypeError::new(
+                name.span,
+                format!(
+                    "Cannot call fn '{}' of another actor, only its behaviours",
+                    name.lexeme
+                ),
+            )
+            .with_note("declare it with 'be' to make it callable from outside the actor");
+            self.errors.push(err);
+            return Type::Unknown;
+        }
+        self.resolve_call(&name.lexeme, &overloads, arguments, name.span)
+    }
+
+    // Mirrors the runtime's overload resolution (see `overload::resolve`).
+    // A union argument is fine as long as every type it may hold has an
+    // overload, the runtime dispatches on the value it actually holds.
+    fn resolve_call(
+        &mut self,
+        name: &str,
+        overloads: &[Signature],
+        arguments: &[Type],
+        span: Span,
+    ) -> Type {
+        if let [signature] = overloads {
+            if signature.params.len() != arguments.len() {
+                self.error(
+                    span,
+                    format!(
+                        "Expected {} arguments but got {}",
+                        signature.params.len(),
+                        arguments.len()
+                    ),
+                );
+                return Type::Unknown;
+            }
+        }
+
+        let mut combinations: Vec<Vec<Type>> = vec![vec![]];
+        for argument in arguments {
+            combinations = combinations
+                .into_iter()
+                .flat_map(|combination| {
+                    argument.members().into_iter().map(move |member| {
+                        let mut combination = combination.clone();
+                        combination.push(member);
+                        combination
+                    })
+                })
+                .collect();
+            if combinations.len() > MAX_COMBINATIONS {
+                return Type::Unknown;
+            }
+        }
+
+        let mut returns = vec![];
+        for combination in combinations {
+            let applicable: Vec<&Signature> = overloads
+                .iter()
+                .filter(|signature| {
+                    signature.params.len() == combination.len()
+                        && combination
+                            .iter()
+                            .zip(&signature.params)
+                            .all(|(argument, param)| argument.is_assignable_to(param))
+                })
+                .collect();
+            let most_specific = applicable.iter().map(|s| s.typed_params).max();
+            let best: Vec<&Signature> = applicable
+                .into_iter()
+                .filter(|s| Some(s.typed_params) == most_specific)
+                .collect();
+
+            let types: Vec<String> = combination.iter().map(|t| t.to_string()).collect();
+            match best.as_slice() {
+                [signature] => returns.push(signature.returns.clone()),
+                [] => {
+                    let err = TypeError::new(
+                        span,
+                        format!("No overload of '{}' accepts ({})", name, types.join(", ")),
+                    )
+                    .with_note(&candidates_note(overloads.iter()));
+                    self.errors.push(err);
+                    return Type::Unknown;
+                }
+                // Which one runs depends on values only known at runtime.
+                _ if combination.contains(&Type::Unknown) => returns.push(Type::Unknown),
+                ambiguous => {
+                    let err = TypeError::new(
+                        span,
+                        format!(
+                            "Ambiguous call to '{}', {} overloads accept ({})",
+                            name,
+                            ambiguous.len(),
+                            types.join(", ")
+                        ),
+                    )
+                    .with_note(&candidates_note(ambiguous.iter().copied()));
+                    self.errors.push(err);
+                    return Type::Unknown;
+                }
+            }
+        }
+        Type::union(returns)
+    }
+
+    fn binary(&mut self, left: &Type, operator: &Token, right: &Type) -> Type {
+        let numeric = left.is_numeric() && right.is_numeric();
+        let result = match operator.token_type {
+            TokenType::EqualEqual | TokenType::BangEqual => Some(Type::Bool),
+            _ if *left == Type::Unknown || *right == Type::Unknown => Some(Type::Unknown),
+            TokenType::Plus if *left == Type::String && *right == Type::String => {
+                Some(Type::String)
+            }
+            // Dividing Ints truncates, like the runtime does. Each pair of
+            // members of union operands gives its own result.
+            TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash if numeric => {
+                let mut results = vec![];
+                for l in left.members() {
+                    for r in right.members() {
+                        results.push(if l == Type::Int && r == Type::Int {
+                            Type::Int
+                        } else {
+                            Type::Float
+                        });
+                    }
+                }
+                Some(Type::union(results))
+            }
+            TokenType::Greater
+            | TokenType::GreaterEqual
+            | TokenType::Less
+            | TokenType::LessEqual
+                if numeric =>
+            {
+                Some(Type::Bool)
+            }
+            _ => None,
+        };
+
+        match result {
+            Some(result) => result,
+            None => {
+                self.error(
+                    operator.span,
+                    format!(
+                        "Operator '{}' cannot be applied to {} and {}",
+                        operator.lexeme, left, right
+                    ),
+                );
+                Type::Unknown
+            }
+        }
+    }
+}
+
+fn literal_type(literal: &LiteralValue) -> Type {
+    match literal {
+        LiteralValue::Int(_) => Type::Int,
+        LiteralValue::Float(_) => Type::Float,
+        LiteralValue::StringValue(_) => Type::String,
+        LiteralValue::True | LiteralValue::False => Type::Bool,
+        LiteralValue::Nil => Type::Nil,
+    }
+}
+
+// Types written without a capability: values of the built in types never
+// change, actors can only be sent messages.
+fn default_capability(t: &Type) -> Option<Capability> {
+    match t {
+        Type::Int
+        | Type::Float
+        | Type::String
+        | Type::Bool
+        | Type::Nil
+        | Type::Function(_)
+        | Type::Module(_) => Some(Capability::Val),
+        Type::Actor(_) | Type::ActorClass(_) => Some(Capability::Tag),
+        Type::Union(types) | Type::Tuple(types) => {
+            types.iter().try_fold(Capability::Val, |joined, t| {
+                Some(joined.join(default_capability(t)?))
+            })
+        }
+        Type::Record(fields) => fields.iter().try_fold(Capability::Val, |joined, (_, t)| {
+            Some(joined.join(default_capability(t)?))
+        }),
+        Type::Unknown => None,
+    }
+}
+
+// The types every program knows about, besides its actors.
+fn builtin_type(name: &str) -> Option<Type> {
+    match name {
+        "Int" => Some(Type::Int),
+        "Float" => Some(Type::Float),
+        "Number" => Some(Type::Union(vec![Type::Int, Type::Float])),
+        "String" => Some(Type::String),
+        "Bool" => Some(Type::Bool),
+        "Nil" => Some(Type::Nil),
+        "Fn" => Some(Type::Function(vec![])),
+        _ => None,
+    }
+}
+
+fn native_signature(native: &NativeFunction) -> Signature {
+    let resolve = |name: &str| builtin_type(name).unwrap_or(Type::Unknown);
+    Signature {
+        kind: FunctionKind::Function,
+        params: native.params.iter().map(|p| resolve(p)).collect(),
+        returns: resolve(native.returns),
+        // Natives keep no reference to their arguments, any will do.
+        capabilities: vec![Some(Capability::Tag); native.params.len()],
+        returns_capability: None,
+        typed_params: native.params.iter().filter(|p| **p != "_").count(),
+        label: format!("{}({})", native.name, native.params.join(", ")),
+        native: true,
+    }
+}
+
+fn callee_name(callee: &Expr) -> String {
+    match callee {
+        Expr::Variable { name, .. } | Expr::Get { name, .. } => name.lexeme.clone(),
+        _ => "function".to_string(),
+    }
+}
+
+fn candidates_note<'a>(candidates: impl Iterator<Item = &'a Signature>) -> String {
+    let labels: Vec<&str> = candidates.map(|s| s.label.as_str()).collect();
+    format!("candidates are {}", labels.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    fn check(source: &str) -> Result<(), Vec<TypeError>> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        Checker::new().check(&statements)
+    }
+
+    fn messages(source: &str) -> Vec<String> {
+        match check(source) {
+            Ok(()) => vec![],
+            Err(errors) => errors.into_iter().map(|e| e.message).collect(),
+        }
+    }
+
+    #[test]
+    fn infers_local_types() {
+        assert_eq!(
+            messages("let a = 1; let b = a + 2.5; let c: Float = b;"),
+            [] as [&str; 0]
+        );
+        assert_eq!(
+            messages("let a = 1; let b: String = a;"),
+            ["Mismatched types: expected String, found Int"]
+        );
+        assert_eq!(
+            messages("let mut a = \"lok\"; a = 1;"),
+            ["Mismatched types: expected String, found Int"]
+        );
+        assert_eq!(
+            messages("let a = \"lok\" - 1;"),
+            ["Operator '-' cannot be applied to String and Int"]
+        );
+    }
+
+    #[test]
+    fn arithmetic_on_unions_types_each_member() {
+        assert_eq!(
+            messages(
+                "fn f(x: Int) -> Int { x }
+                 fn f(x: Float) -> Float { x }
+                 fn g(n: Number, i: Int) {
+                     let a: Int = f(i - i);
+                     let b: Number = f(n + i);
+                     let c: Float = n * 2.5;
+                     let d: Float = n + i;
+                 }"
+            ),
+            ["Mismatched types: expected Float, found (Int | Float)"]
+        );
+    }
+
+    #[test]
+    fn int_and_float_literals_have_their_own_types() {
+        assert_eq!(
+            messages("let a: Int = 7 / 2; let b: Float = 7.0 / 2; let c: Float = 1.0;"),
+            [] as [&str; 0]
+        );
+        assert_eq!(
+            messages("let a: Int = 1.0; let b: Int = 1 * 0.5;"),
+            [
+                "Mismatched types: expected Int, found Float",
+                "Mismatched types: expected Int, found Float"
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_annotated_parameters() {
+        let source = "
+            fn add(a: Int, b: Int) -> Int { a + b }
+            add(1, 2);
+            add(1, \"2\");
+            add(1);
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "No overload of 'add' accepts (Int, String)",
+                "Expected 2 arguments but got 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_return_types() {
+        let source = "
+            fn name() -> String { return 1; }
+            fn age() -> Int { \"twenty\" }
+            fn nothing() -> Int { let a = 1; }
+            fn fine(x) -> Int { if_positive(x); return x; }
+            fn if_positive(x) { x }
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "Expected 'name' to return String, found Int",
+                "Expected 'age' to return Int, found String",
+                "Expected 'nothing' to return Int, found Nil",
+            ]
+        );
+    }
+
+    #[test]
+    fn unannotated_code_is_not_rejected() {
+        let source = "
+            fn twice(f, x) { f(f(x)) }
+            fn inc(x) { x + 1 }
+            let mut result = nil;
+            result = twice(inc, 1);
+            result + 1;
+        ";
+        assert_eq!(messages(source), [] as [&str; 0]);
+    }
+
+    #[test]
+    fn unions_narrow_on_assignment() {
+        let source = "
+            let mut age: (Int | String) = 1;
+            age + 1;
+            age = \"one\";
+            age + \"!\";
+            age - 1;
+        ";
+        assert_eq!(
+            messages(source),
+            ["Operator '-' cannot be applied to String and Int"]
+        );
+
+        let source = "fn f(x: (Int | String)) { x + 1 }";
+        assert_eq!(
+            messages(source),
+            ["Operator '+' cannot be applied to (Int | String) and Int"]
+        );
+    }
+
+    #[test]
+    fn assignments_inside_functions_widen_outer_bindings() {
+        let source = "
+            let mut age: (Int | String) = 1;
+            fn reset() { age = \"unknown\"; }
+            age + 1;
+        ";
+        assert_eq!(
+            messages(source),
+            ["Operator '+' cannot be applied to (Int | String) and Int"]
+        );
+    }
+
+    #[test]
+    fn union_arguments_need_an_overload_for_every_member() {
+        let source = "
+            actor Main {
+                let mut age = 23;
+                be update_age(by_how_much: (Int | String)) {
+                    self.updating_age(by_how_much);
+                }
+                be reset(to: (Int | Bool)) {
+                    self.updating_age(to);
+                }
+                fn updating_age(by: Int) { self.age = self.age + by; }
+                fn updating_age(by: String) { self.age = self.age + 1; }
+            }
+        ";
+        let errors = check(source).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].message,
+            "No overload of 'updating_age' accepts (Bool)"
+        );
+        assert_eq!(
+            errors[0].note.as_deref(),
+            Some("candidates are updating_age(by: Int), updating_age(by: String)")
+        );
+    }
+
+    #[test]
+    fn ambiguous_calls_are_reported() {
+        let source = "
+            fn f(a: Int, b) {}
+            fn f(a, b: Int) {}
+            f(1, 2);
+            fn g(a, b) { f(a, b) }
+        ";
+        assert_eq!(
+            messages(source),
+            ["Ambiguous call to 'f', 2 overloads accept (Int, Int)"]
+        );
+    }
+
+    #[test]
+    fn checks_actor_members() {
+        let source = "
+            actor Counter {
+                let limit: Int = 10;
+                let mut count = 0;
+                be increment() { self.count = self.count + \"1\"; }
+                be reset() { self.limit = 0; }
+                fn secret() { self.count }
+            }
+            let counter = Counter();
+            counter.increment();
+            counter.secret();
+            counter.count;
+            counter.decrement();
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "Operator '+' cannot be applied to Int and String",
+                "Cannot assign twice to immutable field 'limit'",
+                "Cannot call fn 'secret' of another actor, only its behaviours",
+                "Cannot access field 'count' of another actor 'Counter'",
+                "Actor 'Counter' has no method 'decrement'",
+            ]
+        );
+    }
+
+    #[test]
+    fn checks_pipes_through_module_functions() {
+        let source = "
+            let newly: Int = \" 42 \"
+                |> String.trim_string()
+                |> String.parse_to_int();
+            fn add(a: Int, b: Int) -> Int { a + b }
+            let sum: Int = newly |> add(1);
+            1 |> String.length();
+            \"a\" |> String.reverse();
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "No overload of 'String.length' accepts (Int)",
+                "Module 'String' has no function 'reverse'"
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_unknown_names() {
+        assert_eq!(
+            messages("fn f(x: Strin) {} let a = b; self;"),
+            [
+                "Unknown type 'Strin'",
+                "Undefined variable 'b'",
+                "Cannot use 'self' outside of an actor"
+            ]
+        );
+    }
+
+    #[test]
+    fn functions_can_be_used_before_their_declaration() {
+        let source = "
+            fn main() -> Int { helper(1) }
+            fn helper(x: Int) -> Int { x }
+        ";
+        assert_eq!(messages(source), [] as [&str; 0]);
+    }
+
+    fn warnings(source: &str) -> Vec<String> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        let statements = Parser::new(tokens).parse().unwrap();
+        let mut checker = Checker::new();
+        checker.check(&statements).unwrap();
+        checker
+            .take_warnings()
+            .into_iter()
+            .map(|w| w.message)
+            .collect()
+    }
+
+    #[test]
+    fn type_patterns_narrow_their_bindings() {
+        let source = "
+            fn describe(x: (Int | String | Nil)) -> (Int | String) {
+                match x {
+                    n: Int => n + 1,
+                    s: String => s + \"!\",
+                    nil => 0
+                }
+            }
+            fn wrong(x: (Int | String)) {
+                match x {
+                    n: Int => n + \"!\",
+                    b: Bool => b,
+                    \"a\" => 1,
+                    2.5 => 2,
+                    (a, b) => a
+                }
+            }
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "Operator '+' cannot be applied to Int and String",
+                "Pattern 'b: Bool' can never match (Int | String)",
+                "Pattern '2.5' can never match (Int | String)",
+                "Pattern '(a, b)' can never match (Int | String)",
+            ]
+        );
+    }
+
+    #[test]
+    fn matches_type_tuples_and_records() {
+        let source = "
+            let point = (1, \"a\");
+            let total: Int = match point { (x, _) => x };
+            let person = {name: \"lok\", age: 3};
+            let name: String = person.name;
+            let age: String = match person { {age} => age };
+            person.height;
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "Mismatched types: expected String, found Int",
+                "Record has no field 'height'"
+            ]
+        );
+    }
+
+    #[test]
+    fn non_exhaustive_matches_on_unions_are_warned_about() {
+        let source = "
+            fn f(x: (Int | String | Nil)) {
+                match x {
+                    n: Int => n,
+                    s: String if s == \"a\" => s
+                }
+            }
+            fn g(x: (Bool | Nil)) {
+                match x { true => 1, false => 2, nil => 3 }
+            }
+            fn h(x: (Int | String)) {
+                match x { n: Int => n, other => other }
+            }
+            fn i(x: Int) {
+                match x { 1 => 1 }
+            }
+        ";
+        assert_eq!(
+            warnings(source),
+            ["Non-exhaustive match, (String | Nil) not covered"]
+        );
+    }
+
+    #[test]
+    fn only_sendable_capabilities_cross_behaviours() {
+        let source = "
+            actor Log {
+                be log(line: String iso) {}
+                be share(line: String ref) {}
+                be any(line) {}
+            }
+            fn main(log: Log, shared: String ref, kept: String val) {
+                log.any(shared);
+                log.any(kept);
+                log.any(log);
+                log.log(kept);
+            }
+        ";
+        let errors = check(source).unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Behaviour parameter 'line' is ref, which cannot be sent to another actor",
+                "Cannot send a ref value to behaviour 'any'",
+                "Capability mismatch: expected iso, found val",
+            ]
+        );
+        assert_eq!(
+            errors[1].note.as_deref(),
+            Some("only iso, val and tag values can cross a behaviour boundary")
+        );
+        assert_eq!(
+            errors[2].note.as_deref(),
+            Some("a val value can only be used as val, box or tag")
+        );
+    }
+
+    #[test]
+    fn iso_references_are_moved_with_consume() {
+        let source = "
+            actor Log {
+                be log(line: String iso) {}
+            }
+            let log = Log();
+            let line: String iso = \"hello\";
+            log.log(line);
+            let copy = line;
+            copy |> log.log();
+            log.log(consume line);
+            line;
+            let mut again: String iso = \"a\";
+            log.log(consume again);
+            again = \"b\";
+            again |> String.length();
+        ";
+        let errors = check(source).unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Cannot copy the iso reference 'line'",
+                "Capability mismatch: expected iso, found tag",
+                "Cannot use 'line' after it was consumed",
+            ]
+        );
+        assert_eq!(
+            errors[0].note.as_deref(),
+            Some("an iso value has only one reference, write 'consume line' to move it instead")
+        );
+    }
+
+    #[test]
+    fn iso_parameters_are_not_copied() {
+        assert_eq!(
+            messages("fn g(s: String iso) { let t: String iso = s; let u: String iso = s; }"),
+            [
+                "Cannot copy the iso reference 's'",
+                "Cannot copy the iso reference 's'"
+            ]
+        );
+        assert_eq!(
+            messages("fn g(s: String iso) { let t: String val = s; }"),
+            ["Cannot copy the iso reference 's'"]
+        );
+        assert_eq!(
+            messages(
+                "actor A { be take(s: String iso) {} }
+                 fn g(s: String iso) { A().take(s); A().take(s); }"
+            ),
+            [
+                "Cannot copy the iso reference 's'",
+                "Cannot copy the iso reference 's'"
+            ]
+        );
+        assert_eq!(
+            messages(
+                "actor A { be take(s: String iso) {} }
+                 fn g(s: String iso) { let t: String iso = consume s; A().take(consume t); }"
+            ),
+            [] as [&str; 0]
+        );
+    }
+
+    #[test]
+    fn ref_parameters_are_not_sent_to_spawned_actors() {
+        assert_eq!(
+            messages("actor A { be take(s: String) {} } fn g(s: String ref) { A().take(s); }"),
+            ["Capability mismatch: expected val, found ref"]
+        );
+        assert_eq!(
+            messages("actor A { be take(s) {} } fn g(s: String ref) { (A()).take(s); }"),
+            ["Cannot send a ref value to behaviour 'take'"]
+        );
+    }
+
+    #[test]
+    fn untyped_parameters_are_val() {
+        assert_eq!(
+            messages(
+                "fn f(x) { let t: String ref = x; }
+                 actor A { let mut r: String ref = \"\"; be take(s) { self.r = s; } }"
+            ),
+            [
+                "Capability mismatch: expected ref, found val",
+                "Capability mismatch: expected ref, found val"
+            ]
+        );
+        assert_eq!(
+            messages(
+                "fn f(x) {}
+                 fn g(s: String ref, t: String iso) { f(s); t |> f; }"
+            ),
+            [
+                "Capability mismatch: expected val, found ref",
+                "Cannot copy the iso reference 't'"
+            ]
+        );
+        assert!(messages(
+            "fn f(x) {}
+             actor A { be go(s: String iso) { f(self); f(A()); f(1); f(\"a\"); f(consume s); } }"
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn capabilities_of_returns_and_fields() {
+        let source = "
+            fn fresh() -> String iso { \"new\" }
+            fn keep(s: String iso) -> String iso { s }
+            fn give(s: String iso) -> String iso { return consume s; }
+            fn freeze(s: String iso) -> String val { consume s }
+            let mut outer: String iso = fresh();
+            fn steal() { consume outer; }
+            actor Buffer {
+                let mut line: String trn = \"\";
+                fn read() -> String ref { self.line }
+                fn write(s: String ref) { self.line = s; }
+            }
+        ";
+        assert_eq!(
+            messages(source),
+            [
+                "Cannot copy the iso reference 's'",
+                "Cannot consume 'outer', it is declared outside of this function",
+                "Capability mismatch: expected ref, found box",
+                "Capability mismatch: expected trn, found ref",
+            ]
+        );
+    }
+
+    #[test]
+    fn types_expressions_in_the_checked_scope() {
+        let tokens = Scanner::new("let a = 1; fn half(x: Int) -> Float { x / 2.0 }")
+            .scan_tokens()
+            .unwrap();
+        let mut checker = Checker::new();
+        checker
+            .check(&Parser::new(tokens).parse().unwrap())
+            .unwrap();
+
+        let type_of = |checker: &mut Checker, source: &str| {
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            match &Parser::new(tokens).parse().unwrap()[0] {
+                Stmt::Expression { expression, .. } => checker
+                    .type_of(expression)
+                    .map(|t| t.to_string())
+                    .map_err(|errors| errors[0].message.clone()),
+                _ => panic!("Expected an expression"),
+            }
+        };
+        assert_eq!(
+            type_of(&mut checker, "(a, half(a));"),
+            Ok("(Int, Float)".to_string())
+        );
+        assert_eq!(
+            type_of(&mut checker, "a + \"s\";"),
+            Err("Operator '+' cannot be applied to Int and String".to_string())
+        );
+    }
+}
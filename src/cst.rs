@@ -0,0 +1,168 @@
+use crate::parser::{ParseError, Parser};
+use crate::scanner::{ScanError, Scanner, Span, Token, TokenType};
+use crate::stmt::Stmt;
+
+// The lossless layer under the AST. It is flat, there are no nodes between
+// the tokens and the AST: every token keeps the whitespace and comments
+// before it, so the trivia and lexemes of the tokens, in order, are the
+// source byte for byte. The AST is derived from the same tokens, its spans
+// point back into them.
+pub struct SyntaxTree {
+    tokens: Vec<Token>,
+    errors: Vec<ScanError>,
+}
+
+impl SyntaxTree {
+    // Lossless even when the source has errors, the text that couldn't be
+    // scanned is kept as skipped trivia.
+    pub fn scan(source: &str) -> Self {
+        let (tokens, errors) = Scanner::new(source).scan_lossless();
+        Self { tokens, errors }
+    }
+
+    // Only a source without scan errors is parsed.
+    pub fn parse(source: &str) -> Result<Self, Vec<ScanError>> {
+        let tree = Self::scan(source);
+        if !tree.errors.is_empty() {
+            return Err(tree.errors);
+        }
+        Ok(tree)
+    }
+
+    // The REPL runs `1 + 2` as `1 + 2;`. The `;` is implied: it has no text
+    // and sits right after the last token, so the tree stays lossless and
+    // diagnostics only quote what was typed.
+    pub fn imply_semicolon(&mut self) {
+        let last = match self.tokens.iter().rev().nth(1) {
+            Some(last) => last,
+            None => return,
+        };
+        if matches!(
+            last.token_type,
+            TokenType::Semicolon | TokenType::RightBrace
+        ) {
+            return;
+        }
+        let (line, column) = match last.lexeme.rfind('\n') {
+            Some(newline) => (
+                last.span.line + last.lexeme.matches('\n').count(),
+                last.lexeme[newline + 1..].chars().count() + 1,
+            ),
+            None => (
+                last.span.line,
+                last.span.column + last.lexeme.chars().count(),
+            ),
+        };
+        let span = Span::new(line, column, last.span.end, last.span.end);
+        let semicolon = Token::new(TokenType::Semicolon, String::new(), None, span);
+        self.tokens.insert(self.tokens.len() - 1, semicolon);
+    }
+
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    // Ends with EOF, which holds the trivia at the end of the source.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    pub fn ast(&self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        Parser::new(self.tokens.clone()).parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::TriviaKind;
+
+    fn to_source(tree: &SyntaxTree) -> String {
+        let mut source = String::new();
+        for token in tree.tokens() {
+            for trivia in &token.leading_trivia {
+                source.push_str(&trivia.text);
+            }
+            source.push_str(&token.lexeme);
+        }
+        source
+    }
+
+    #[test]
+    fn round_trips_the_source() {
+        let source = "/// The main actor.\n\
+                      /// Keeps an age.\n\
+                      actor Main {\r\n\
+                      \tlet mut age = 0x17; // years\n\
+                      \t/* nested /* block */ comment */\n\
+                      \tbe greet(name: String iso) { print(\"hé ${name}\\n\"); }\n\
+                      }\n\
+                      //// not a doc comment\n";
+        let tree = SyntaxTree::parse(source).unwrap();
+        assert_eq!(to_source(&tree), source);
+
+        let eof = tree.tokens().last().unwrap();
+        let kinds: Vec<TriviaKind> = eof.leading_trivia.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TriviaKind::Whitespace,
+                TriviaKind::LineComment,
+                TriviaKind::Whitespace
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_what_could_not_be_scanned() {
+        let source = "let a = 1 @ 2;\nlet b = \"bad \\q\";\n/* open";
+        let tree = SyntaxTree::scan(source);
+        assert_eq!(to_source(&tree), source);
+        let messages: Vec<&str> = tree.errors().iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Unrecognized char: @",
+                "Invalid escape sequence '\\q'",
+                "Unterminated block comment, expected '*/'"
+            ]
+        );
+        let skipped: Vec<&str> = tree
+            .tokens()
+            .iter()
+            .flat_map(|token| &token.leading_trivia)
+            .filter(|trivia| trivia.kind == TriviaKind::Skipped)
+            .map(|trivia| trivia.text.as_str())
+            .collect();
+        assert_eq!(skipped, ["@", "\"bad \\q\"", "/* open"]);
+        assert!(SyntaxTree::parse(source).is_err());
+    }
+
+    #[test]
+    fn doc_comments_attach_to_the_next_declaration() {
+        let source = "/// The main actor.\n\
+                      ///Keeps an age.\n\
+                      actor Main {\n\
+                      /// Years since birth.\n\
+                      let mut age = 0;\n\
+                      // Not a doc comment.\n\
+                      be grow() { self.age = self.age + 1; }\n\
+                      }";
+        let statements = SyntaxTree::parse(source).unwrap().ast().unwrap();
+        let Stmt::Actor {
+            doc,
+            fields,
+            methods,
+            ..
+        } = &statements[0]
+        else {
+            panic!("Expected an actor");
+        };
+        assert_eq!(doc.as_deref(), Some("The main actor.\nKeeps an age."));
+        match &fields[0] {
+            Stmt::Let { doc, .. } => assert_eq!(doc.as_deref(), Some("Years since birth.")),
+            _ => panic!("Expected a field"),
+        }
+        assert_eq!(methods[0].doc, None);
+    }
+}
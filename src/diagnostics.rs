@@ -0,0 +1,261 @@
+use crate::checker::TypeError;
+use crate::interpreter::RuntimeError;
+use crate::parser::ParseError;
+use crate::scanner::{ScanError, Span};
+
+use std::fmt;
+
+// Error codes, one per compiler phase:
+//   E0001  lexical error reported by the scanner
+//   E0002  syntax error reported by the parser
+//   E0003  error raised while running the program
+//   E0004  type error reported by the checker, before running
+//   W0001  warning reported by the checker, the program still runs
+pub const SCAN_ERROR: &str = "E0001";
+pub const PARSE_ERROR: &str = "E0002";
+pub const RUNTIME_ERROR: &str = "E0003";
+pub const TYPE_ERROR: &str = "E0004";
+pub const TYPE_WARNING: &str = "W0001";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: &str, span: Span) -> Self {
+        Self {
+            severity: Severity::Error,
+            code,
+            message: message.to_string(),
+            span,
+            notes: vec![],
+        }
+    }
+
+    pub fn warning(code: &'static str, message: &str, span: Span) -> Self {
+        Self {
+            severity: Severity::Warning,
+            ..Self::error(code, message, span)
+        }
+    }
+
+    // Checker warnings share `TypeError` with its errors.
+    pub fn type_warning(warning: &TypeError) -> Self {
+        let diagnostic = Diagnostic::warning(TYPE_WARNING, &warning.message, warning.span);
+        match &warning.note {
+            Some(note) => diagnostic.with_note(note),
+            None => diagnostic,
+        }
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    pub fn render(&self, source: &str, file: &str, format: ErrorFormat) -> String {
+        match format {
+            ErrorFormat::Human => self.render_human(source, file),
+            ErrorFormat::Json => self.to_json(file),
+        }
+    }
+
+    // Renders the diagnostic the way rustc does:
+    //
+    //   error[E0002]: Expected expression
+    //    --> main.lok:1:9
+    //     |
+    //   1 | let a = ;
+    //     |         ^
+    //
+    // Spans running over several lines are underlined up to the end of their
+    // first line. Columns and underlines count characters, not bytes.
+    pub fn render_human(&self, source: &str, file: &str) -> String {
+        let mut out = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+
+        let line_number = self.span.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        out.push_str(&format!("{}--> {}:{}\n", gutter, file, self.span));
+
+        if let Some(line) = source.lines().nth(self.span.line.saturating_sub(1)) {
+            let line_width = line.chars().count();
+            let offset = self.span.column.saturating_sub(1).min(line_width);
+            let width = source
+                .get(self.span.start..self.span.end)
+                .map_or(0, |text| text.chars().take_while(|c| *c != '\n').count())
+                .min(line_width - offset)
+                .max(1);
+            out.push_str(&format!("{} |\n", gutter));
+            out.push_str(&format!("{} | {}\n", line_number, line));
+            out.push_str(&format!(
+                "{} | {}{}\n",
+                gutter,
+                " ".repeat(offset),
+                "^".repeat(width)
+            ));
+        }
+        for note in &self.notes {
+            out.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        out
+    }
+
+    // One JSON object per diagnostic, so tools can read the output line by line.
+    pub fn to_json(&self, file: &str) -> String {
+        let notes: Vec<String> = self.notes.iter().map(|note| json_string(note)).collect();
+        format!(
+            "{{\"severity\":{},\"code\":{},\"message\":{},\"file\":{},\"span\":{{\"line\":{},\"column\":{},\"start\":{},\"end\":{}}},\"notes\":[{}]}}",
+            json_string(&self.severity.to_string()),
+            json_string(self.code),
+            json_string(&self.message),
+            json_string(file),
+            self.span.line,
+            self.span.column,
+            self.span.start,
+            self.span.end,
+            notes.join(",")
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+impl From<&ScanError> for Diagnostic {
+    fn from(err: &ScanError) -> Self {
+        Diagnostic::error(SCAN_ERROR, &err.message, err.span)
+    }
+}
+
+impl From<&ParseError> for Diagnostic {
+    fn from(err: &ParseError) -> Self {
+        Diagnostic::error(PARSE_ERROR, &err.message, err.span)
+    }
+}
+
+impl From<&RuntimeError> for Diagnostic {
+    fn from(err: &RuntimeError) -> Self {
+        let diagnostic = Diagnostic::error(RUNTIME_ERROR, &err.message, err.span);
+        match &err.note {
+            Some(note) => diagnostic.with_note(note),
+            None => diagnostic,
+        }
+    }
+}
+
+impl From<&TypeError> for Diagnostic {
+    fn from(err: &TypeError) -> Self {
+        let diagnostic = Diagnostic::error(TYPE_ERROR, &err.message, err.span);
+        match &err.note {
+            Some(note) => diagnostic.with_note(note),
+            None => diagnostic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_points_at_span() {
+        let source = "let a = 1;\nlet b = a +* 2;\n";
+        let diagnostic =
+            Diagnostic::error(PARSE_ERROR, "Expected expression", Span::new(2, 12, 22, 23));
+        assert_eq!(
+            diagnostic.render_human(source, "main.lok"),
+            "error[E0002]: Expected expression\n \
+             --> main.lok:2:12\n  \
+             |\n\
+             2 | let b = a +* 2;\n  \
+             |            ^\n"
+        );
+    }
+
+    #[test]
+    fn render_underlines_whole_span_and_notes() {
+        let source = "age = 24;";
+        let diagnostic = Diagnostic::error(RUNTIME_ERROR, "Immutable", Span::new(1, 1, 0, 3))
+            .with_note("declare it with 'let mut'");
+        let rendered = diagnostic.render_human(source, "main.lok");
+        assert!(rendered.contains("1 | age = 24;\n  | ^^^\n"));
+        assert!(rendered.ends_with("  = note: declare it with 'let mut'\n"));
+    }
+
+    #[test]
+    fn render_multiline_span_stops_at_end_of_line() {
+        let source = "\"abc\ndef";
+        let diagnostic = Diagnostic::error(SCAN_ERROR, "Unterminated", Span::new(1, 1, 0, 8));
+        assert!(diagnostic
+            .render_human(source, "main.lok")
+            .contains("1 | \"abc\n  | ^^^^\n"));
+    }
+
+    #[test]
+    fn render_counts_characters() {
+        let source = "let café = \"ü\" + 1;";
+        let diagnostic = Diagnostic::error(TYPE_ERROR, "Mismatch", Span::new(1, 12, 12, 16));
+        assert!(diagnostic
+            .render_human(source, "main.lok")
+            .contains("1 | let café = \"ü\" + 1;\n  |            ^^^\n"));
+    }
+
+    #[test]
+    fn render_warning() {
+        let diagnostic = Diagnostic::warning(TYPE_WARNING, "Non-exhaustive", Span::new(1, 1, 0, 5));
+        assert!(diagnostic
+            .render_human("match", "main.lok")
+            .starts_with("warning[W0001]: Non-exhaustive\n"));
+    }
+
+    #[test]
+    fn json_output() {
+        let diagnostic =
+            Diagnostic::error(SCAN_ERROR, "Unrecognized char: \"", Span::new(1, 2, 1, 2))
+                .with_note("a\tb");
+        assert_eq!(
+            diagnostic.to_json("dir\\main.lok"),
+            r#"{"severity":"error","code":"E0001","message":"Unrecognized char: \"","file":"dir\\main.lok","span":{"line":1,"column":2,"start":1,"end":2},"notes":["a\tb"]}"#
+        );
+    }
+}
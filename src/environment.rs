@@ -0,0 +1,71 @@
+use crate::interpreter::Value;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+struct Binding {
+    value: Value,
+    mutable: bool,
+}
+
+pub enum AssignError {
+    Undefined,
+    Immutable,
+}
+
+// Environments are shared between the worker threads running actors, hence
+// `Arc<Mutex<..>>` rather than `Rc<RefCell<..>>`.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Binding>,
+    enclosing: Option<Arc<Mutex<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_enclosing(enclosing: Arc<Mutex<Environment>>) -> Self {
+        Self {
+            values: HashMap::new(),
+            enclosing: Some(enclosing),
+        }
+    }
+
+    // Redeclaring a name in the same scope shadows the previous binding.
+    pub fn define(&mut self, name: &str, value: Value, mutable: bool) {
+        self.values
+            .insert(name.to_string(), Binding { value, mutable });
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.values.get(name) {
+            Some(binding) => Some(binding.value.clone()),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.lock().unwrap().get(name),
+                None => None,
+            },
+        }
+    }
+
+    // Looks `name` up in this scope only, ignoring the enclosing ones.
+    pub fn get_local(&self, name: &str) -> Option<Value> {
+        self.values.get(name).map(|binding| binding.value.clone())
+    }
+
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), AssignError> {
+        match self.values.get_mut(name) {
+            Some(binding) if binding.mutable => {
+                binding.value = value;
+                Ok(())
+            }
+            Some(_) => Err(AssignError::Immutable),
+            None => match &self.enclosing {
+                Some(enclosing) => enclosing.lock().unwrap().assign(name, value),
+                None => Err(AssignError::Undefined),
+            },
+        }
+    }
+}
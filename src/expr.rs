@@ -1,92 +1,245 @@
-use crate::scanner::{self, Token, TokenType};
+use crate::pattern::Pattern;
+use crate::scanner::{self, Span, Token, TokenType};
+use crate::stmt::Stmt;
 
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum LiteralValue {
-    Number(f32),
+    Int(i64),
+    Float(f64),
     StringValue(String),
     True,
     False,
     Nil,
 }
-fn unwrap_as_f32(literal: Option<scanner::LiteralValue>) -> f32 {
+fn unwrap_as_number(literal: Option<scanner::LiteralValue>) -> Result<LiteralValue, String> {
     match literal {
-        Some(scanner::LiteralValue::IntValue(x)) => x as f32,
-        Some(scanner::LiteralValue::FloatValue(x)) => x as f32,
-        _ => panic!("Could not unwrap as f32"),
+        Some(scanner::LiteralValue::IntValue(x)) => Ok(LiteralValue::Int(x)),
+        Some(scanner::LiteralValue::FloatValue(x)) => Ok(LiteralValue::Float(x)),
+        _ => Err("Could not unwrap as a number".to_string()),
     }
 }
-fn unwrap_as_string(literal: Option<scanner::LiteralValue>) -> String {
+fn unwrap_as_string(literal: Option<scanner::LiteralValue>) -> Result<String, String> {
     match literal {
-        Some(scanner::LiteralValue::StringValue(s)) => s.clone(),
-        Some(scanner::LiteralValue::IdentifierValue(s)) => s.clone(),
-        _ => panic!("Could not unwrap as string"),
+        Some(scanner::LiteralValue::StringValue(s)) => Ok(s),
+        _ => Err("Could not unwrap as string".to_string()),
     }
 }
-impl LiteralValue {
-    pub fn to_string(&self) -> String {
+impl fmt::Display for LiteralValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            LiteralValue::Number(x) => x.to_string(),
-            LiteralValue::StringValue(x) => x.clone(),
-            LiteralValue::True => "true".to_string(),
-            LiteralValue::False => "false".to_string(),
-            LiteralValue::Nil => "nil".to_string(),
+            LiteralValue::Int(x) => write!(f, "{}", x),
+            // Always with a fraction, `1.0` isn't the Int `1`.
+            LiteralValue::Float(x) => write!(f, "{:?}", x),
+            LiteralValue::StringValue(x) => write!(f, "{}", x),
+            LiteralValue::True => write!(f, "true"),
+            LiteralValue::False => write!(f, "false"),
+            LiteralValue::Nil => write!(f, "nil"),
         }
     }
-    pub fn from_token(token: Token) -> Self {
+}
+impl LiteralValue {
+    pub fn from_token(token: Token) -> Result<Self, String> {
         match token.token_type {
-            TokenType::Number => Self::Number(unwrap_as_f32(token.literal)),
-            TokenType::StringLit => Self::StringValue(unwrap_as_string(token.literal)),
-            TokenType::False => Self::False,
-            TokenType::True => Self::True,
-            TokenType::Nil => Self::Nil,
-            _ => panic!("Could not create literalvalue from {:?}", token),
+            TokenType::Number => unwrap_as_number(token.literal),
+            TokenType::String => Ok(Self::StringValue(unwrap_as_string(token.literal)?)),
+            TokenType::False => Ok(Self::False),
+            TokenType::True => Ok(Self::True),
+            TokenType::Nil => Ok(Self::Nil),
+            _ => Err(format!(
+                "Could not create literal value from '{}'",
+                token.lexeme
+            )),
         }
     }
 }
 
-pub enum Expr<'a> {
+#[derive(Debug, Clone)]
+pub enum Expr {
     Binary {
-        left: Box<Expr<'a>>,
-        operator: &'a Token,
-        right: Box<Expr<'a>>,
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
     },
     Grouping {
-        expression: Box<Expr<'a>>,
+        expression: Box<Expr>,
+        span: Span,
     },
     Literal {
         value: LiteralValue,
+        span: Span,
     },
     Unary {
-        operator: &'a Token,
-        right: Box<Expr<'a>>,
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Variable {
+        name: Token,
+        span: Span,
+    },
+    Assign {
+        name: Token,
+        value: Box<Expr>,
+        span: Span,
+    },
+    Call {
+        callee: Box<Expr>,
+        paren: Token,
+        arguments: Vec<Expr>,
+        span: Span,
+    },
+    Get {
+        object: Box<Expr>,
+        name: Token,
+        span: Span,
+    },
+    Set {
+        object: Box<Expr>,
+        name: Token,
+        value: Box<Expr>,
+        span: Span,
+    },
+    SelfRef {
+        keyword: Token,
+        span: Span,
+    },
+    // `left |> right`, where `right` is usually a call.
+    Pipe {
+        left: Box<Expr>,
+        operator: Token,
+        right: Box<Expr>,
+        span: Span,
+    },
+    Tuple {
+        elements: Vec<Expr>,
+        span: Span,
+    },
+    Record {
+        fields: Vec<(Token, Expr)>,
+        span: Span,
+    },
+    Match {
+        keyword: Token,
+        value: Box<Expr>,
+        arms: Vec<MatchArm>,
+        span: Span,
+    },
+    // `consume name` moves the value out of a local, which can't be used
+    // again until it is assigned.
+    Consume {
+        keyword: Token,
+        name: Token,
+        span: Span,
     },
 }
 
-impl<'a> Expr<'a> {
-    pub fn to_string(&self) -> String {
+// `pattern if guard => body`, the body is an expression statement or a block.
+#[derive(Debug, Clone)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: Box<Stmt>,
+    pub span: Span,
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Expr::Unary { operator, right } => {
-                let operator_str = operator.lexeme.clone();
-                let right_str = (*right).to_string(); // TODO (*right) seems to recursively call
-                                                      // the function, investigate that.
-                format!("({} {})", operator_str, right_str)
-            }
-            Expr::Literal { value } => format!("{}", value.to_string()),
-            Expr::Grouping { expression } => format!("(group {})", (*expression).to_string()),
+            Expr::Unary {
+                operator, right, ..
+            } => write!(f, "({} {})", operator.lexeme, right),
+            // Quoted, `"a b"` is one string and not two symbols.
+            Expr::Literal {
+                value: LiteralValue::StringValue(s),
+                ..
+            } => write!(f, "{:?}", s),
+            Expr::Literal { value, .. } => write!(f, "{}", value),
+            Expr::Grouping { expression, .. } => write!(f, "(group {})", expression),
             Expr::Binary {
                 left,
                 operator,
                 right,
+                ..
+            } => write!(f, "({} {} {})", operator.lexeme, left, right),
+            Expr::Variable { name, .. } => write!(f, "{}", name.lexeme),
+            Expr::Assign { name, value, .. } => write!(f, "(= {} {})", name.lexeme, value),
+            Expr::Call {
+                callee, arguments, ..
             } => {
-                let operator_str = operator.lexeme.clone();
-                let left_str = (*left).to_string();
-                let right_str = (*right).to_string();
-
-                format!("({} {} {})", operator_str, left_str, right_str)
+                write!(f, "(call {}", callee)?;
+                for argument in arguments {
+                    write!(f, " {}", argument)?;
+                }
+                write!(f, ")")
             }
+            Expr::Get { object, name, .. } => write!(f, "(. {} {})", object, name.lexeme),
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => write!(f, "(= (. {} {}) {})", object, name.lexeme, value),
+            Expr::SelfRef { .. } => write!(f, "self"),
+            Expr::Pipe { left, right, .. } => write!(f, "(|> {} {})", left, right),
+            Expr::Tuple { elements, .. } => {
+                write!(f, "(tuple")?;
+                for element in elements {
+                    write!(f, " {}", element)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Record { fields, .. } => {
+                write!(f, "(record")?;
+                for (name, value) in fields {
+                    write!(f, " ({} {})", name.lexeme, value)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Match { value, arms, .. } => {
+                write!(f, "(match {}", value)?;
+                for arm in arms {
+                    write!(f, " {}", arm)?;
+                }
+                write!(f, ")")
+            }
+            Expr::Consume { name, .. } => write!(f, "(consume {})", name.lexeme),
         }
     }
-    fn print(&self) {
-        println!("{}", self.to_string());
+}
+
+impl fmt::Display for MatchArm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(arm {}", self.pattern)?;
+        if let Some(guard) = &self.guard {
+            write!(f, " (if {})", guard)?;
+        }
+        write!(f, " {})", self.body)
+    }
+}
+
+impl Expr {
+    // The source range covered by the whole expression.
+    pub fn span(&self) -> Span {
+        match self {
+            Expr::Binary { span, .. }
+            | Expr::Grouping { span, .. }
+            | Expr::Literal { span, .. }
+            | Expr::Unary { span, .. }
+            | Expr::Variable { span, .. }
+            | Expr::Assign { span, .. }
+            | Expr::Call { span, .. }
+            | Expr::Get { span, .. }
+            | Expr::Set { span, .. }
+            | Expr::SelfRef { span, .. }
+            | Expr::Pipe { span, .. }
+            | Expr::Tuple { span, .. }
+            | Expr::Record { span, .. }
+            | Expr::Match { span, .. }
+            | Expr::Consume { span, .. } => *span,
+        }
     }
 }
 
@@ -100,31 +253,38 @@ mod tests {
             token_type: TokenType::Minus,
             lexeme: "-".to_string(),
             literal: None,
-            line_number: 1,
+            span: Span::default(),
+            leading_trivia: vec![],
         };
 
         let onetwothree = Expr::Literal {
-            value: LiteralValue::Number(123.0),
+            value: LiteralValue::Int(123),
+            span: Span::default(),
         };
 
         let group = Expr::Grouping {
             expression: Box::from(Expr::Literal {
-                value: LiteralValue::Number(45.67),
+                value: LiteralValue::Float(45.67),
+                span: Span::default(),
             }),
+            span: Span::default(),
         };
         let multi = Token {
             token_type: TokenType::Star,
             lexeme: "*".to_string(),
             literal: None,
-            line_number: 1,
+            span: Span::default(),
+            leading_trivia: vec![],
         };
         let ast = Expr::Binary {
             left: Box::from(Expr::Unary {
                 operator: minus_token,
                 right: Box::from(onetwothree),
+                span: Span::default(),
             }),
             operator: multi,
             right: Box::from(group),
+            span: Span::default(),
         };
         let res = ast.to_string();
         assert_eq!(res, "(* (- 123) (group 45.67))");
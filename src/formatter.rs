@@ -0,0 +1,799 @@
+use crate::cst::SyntaxTree;
+use crate::diagnostics::Diagnostic;
+use crate::expr::{Expr, MatchArm};
+use crate::pattern::Pattern;
+use crate::scanner::{Span, Trivia, TriviaKind};
+use crate::stmt::{Function, Stmt};
+
+const INDENT: &str = "    ";
+
+// Pretty-prints a whole file in the one canonical style:
+//
+//   - four spaces per level, one statement per line, `{` on the line it opens
+//   - a blank line around actors and functions, other blank lines are kept
+//     but never more than one in a row
+//   - pipes with more than one stage put each `|>` on its own line
+//   - literals and interpolated strings are kept as written
+//
+// Comments are kept on their own line before the code that followed them, or
+// at the end of the line they ended. Those inside an expression or a list stay
+// where they are, the code after a `//` comment goes on the next line.
+// Formatting its own output changes nothing.
+pub fn format_source(source: &str) -> Result<String, Vec<Diagnostic>> {
+    let tree = SyntaxTree::parse(source)
+        .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+    let statements = tree
+        .ast()
+        .map_err(|errors| errors.iter().map(Diagnostic::from).collect::<Vec<_>>())?;
+
+    let comments = tree
+        .tokens()
+        .iter()
+        .flat_map(|token| &token.leading_trivia)
+        .filter(|trivia| trivia.kind != TriviaKind::Whitespace)
+        .cloned()
+        .collect();
+    let mut formatter = Formatter {
+        source,
+        line_starts: line_starts(source),
+        comments,
+        next_comment: 0,
+        out: String::new(),
+        indent: 0,
+        previous_line: None,
+    };
+    let members: Vec<Member> = statements.iter().map(Member::Stmt).collect();
+    formatter.members(&members, source.len());
+    Ok(formatter.finish())
+}
+
+// A statement, or an actor's method, which keeps its own list.
+enum Member<'a> {
+    Stmt(&'a Stmt),
+    Method(&'a Function),
+}
+
+impl Member<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Member::Stmt(stmt) => stmt.span(),
+            Member::Method(function) => function.span,
+        }
+    }
+
+    fn is_declaration(&self) -> bool {
+        matches!(
+            self,
+            Member::Method(_) | Member::Stmt(Stmt::Actor { .. } | Stmt::Function(_))
+        )
+    }
+}
+
+struct Formatter<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    comments: Vec<Trivia>,
+    next_comment: usize,
+    out: String,
+    indent: usize,
+    // The source line the last statement or comment ended on, `None` at the
+    // start of a block.
+    previous_line: Option<usize>,
+}
+
+impl Formatter<'_> {
+    fn finish(mut self) -> String {
+        let trimmed = self.out.trim_end().len();
+        self.out.truncate(trimmed);
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    // The statements of a file or block, up to the offset of its end.
+    fn members(&mut self, members: &[Member], end: usize) {
+        self.previous_line = None;
+        let mut previous_declaration = false;
+        for member in members {
+            let span = member.span();
+            // The blank line around a declaration goes before its comments.
+            let declaration = member.is_declaration();
+            if self.previous_line.is_some() && (declaration || previous_declaration) {
+                self.blank_line();
+            }
+            self.comments_before(span.start);
+            self.keep_blank_line(span.line);
+
+            self.start_line();
+            match member {
+                Member::Stmt(stmt) => self.statement(stmt),
+                Member::Method(function) => self.function(function),
+            }
+            self.trailing_comments(span.end);
+            self.out.push('\n');
+            previous_declaration = declaration;
+        }
+        self.comments_before(end);
+    }
+
+    fn statement(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Expression { expression, span } => {
+                self.expression(expression);
+                // The value of a block is its last expression, without ';'.
+                if span.end > expression.span().end {
+                    self.out.push(';');
+                }
+            }
+            Stmt::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                self.out.push_str("let ");
+                if *mutable {
+                    self.out.push_str("mut ");
+                }
+                self.out.push_str(&name.lexeme);
+                if let Some(annotation) = type_annotation {
+                    self.out.push_str(&format!(": {}", annotation));
+                }
+                if let Some(initializer) = initializer {
+                    self.out.push_str(" = ");
+                    self.expression(initializer);
+                }
+                self.out.push(';');
+            }
+            Stmt::Block { statements, span } => self.block(statements, span.start, *span),
+            Stmt::Actor {
+                name,
+                fields,
+                methods,
+                span,
+                ..
+            } => {
+                self.out.push_str(&format!("actor {} ", name.lexeme));
+                let mut members: Vec<Member> = fields.iter().map(Member::Stmt).collect();
+                members.extend(methods.iter().map(Member::Method));
+                members.sort_by_key(|member| member.span().start);
+                let open = self.find_code('{', name.span.end);
+                self.braced(&members, open, *span);
+            }
+            Stmt::Function(function) => self.function(function),
+            Stmt::Return { value, .. } => {
+                self.out.push_str("return");
+                if let Some(value) = value {
+                    self.out.push(' ');
+                    self.expression(value);
+                }
+                self.out.push(';');
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function) {
+        self.out
+            .push_str(&format!("{} {}(", function.kind, function.name.lexeme));
+        let mut params_end = function.name.span.end;
+        for (i, param) in function.params.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.code_comments(param.name.span.start);
+            self.out.push_str(&param.to_string());
+            params_end = param
+                .type_annotation
+                .as_ref()
+                .map_or(param.name.span.end, |annotation| annotation.span().end);
+        }
+        let close = self.find_code(')', params_end);
+        self.inline_comments(close);
+        self.out.push(')');
+        let mut header_end = close;
+        if let Some(return_type) = &function.return_type {
+            self.out.push_str(" -> ");
+            self.code_comments(return_type.span().start);
+            self.out.push_str(&return_type.to_string());
+            header_end = return_type.span().end;
+        }
+        let open = self.find_code('{', header_end);
+        self.inline_comments(open);
+        if !self.out.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+        self.block(&function.body, open, function.span);
+    }
+
+    fn block(&mut self, statements: &[Stmt], open: usize, span: Span) {
+        let members: Vec<Member> = statements.iter().map(Member::Stmt).collect();
+        self.braced(&members, open, span);
+    }
+
+    // `{}` when there is nothing inside, comments included. `open` is the
+    // offset of the `{`.
+    fn braced(&mut self, members: &[Member], open: usize, span: Span) {
+        let has_comments = self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.span.start < span.end);
+        if members.is_empty() && !has_comments {
+            self.out.push_str("{}");
+            return;
+        }
+
+        self.out.push('{');
+        let first = members
+            .first()
+            .map_or(span.end, |member| member.span().start);
+        self.comments_after_brace(open, first);
+        self.out.push('\n');
+        self.indent += 1;
+        self.members(members, span.end);
+        self.indent -= 1;
+        self.start_line();
+        self.out.push('}');
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        self.code_comments(expr.span().start);
+        match expr {
+            Expr::Literal { span, .. } => self.source_text(*span),
+            // Interpolated strings are desugared by the parser, their `+`
+            // spans the whole string.
+            Expr::Binary { operator, span, .. }
+                if self.source[operator.span.start..].starts_with('"') =>
+            {
+                self.source_text(*span)
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                self.expression(left);
+                self.out.push_str(&format!(" {} ", operator.lexeme));
+                self.expression(right);
+            }
+            Expr::Grouping {
+                expression, span, ..
+            } => {
+                self.out.push('(');
+                self.expression(expression);
+                self.inline_comments(span.end - 1);
+                self.out.push(')');
+            }
+            Expr::Unary {
+                operator, right, ..
+            } => {
+                self.out.push_str(&operator.lexeme);
+                self.expression(right);
+            }
+            Expr::Variable { name, .. } => self.out.push_str(&name.lexeme),
+            Expr::Assign { name, value, .. } => {
+                self.out.push_str(&format!("{} = ", name.lexeme));
+                self.expression(value);
+            }
+            Expr::Call {
+                callee,
+                arguments,
+                span,
+                ..
+            } => {
+                self.expression(callee);
+                self.out.push('(');
+                self.list(arguments);
+                self.inline_comments(span.end - 1);
+                self.out.push(')');
+            }
+            Expr::Get { object, name, .. } => {
+                self.expression(object);
+                self.out.push_str(&format!(".{}", name.lexeme));
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                self.expression(object);
+                self.out.push_str(&format!(".{} = ", name.lexeme));
+                self.expression(value);
+            }
+            Expr::SelfRef { .. } => self.out.push_str("self"),
+            Expr::Pipe { .. } => self.pipe(expr),
+            Expr::Tuple { elements, span } => {
+                self.out.push('(');
+                self.list(elements);
+                if elements.len() == 1 {
+                    self.out.push(',');
+                }
+                self.inline_comments(span.end - 1);
+                self.out.push(')');
+            }
+            Expr::Record { fields, span } => {
+                self.out.push('{');
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.code_comments(name.span.start);
+                    self.out.push_str(&name.lexeme);
+                    match value {
+                        Expr::Variable { name: variable, .. } if variable.lexeme == name.lexeme => {
+                        }
+                        value => {
+                            self.out.push_str(": ");
+                            self.expression(value);
+                        }
+                    }
+                }
+                self.inline_comments(span.end - 1);
+                self.out.push('}');
+            }
+            Expr::Match {
+                value, arms, span, ..
+            } => {
+                self.out.push_str("match ");
+                self.expression(value);
+                let open = self.find_code('{', value.span().end);
+                self.inline_comments(open);
+                self.out.push_str(" {");
+                let first = arms.first().map_or(span.end, |arm| arm.span.start);
+                self.comments_after_brace(open, first);
+                self.out.push('\n');
+                self.indent += 1;
+                self.previous_line = None;
+                for arm in arms {
+                    self.comments_before(arm.span.start);
+                    self.keep_blank_line(arm.span.line);
+                    self.start_line();
+                    self.arm(arm);
+                    self.trailing_comments(arm.span.end);
+                    self.out.push('\n');
+                }
+                self.comments_before(span.end);
+                self.indent -= 1;
+                self.start_line();
+                self.out.push('}');
+            }
+            Expr::Consume { name, .. } => self.out.push_str(&format!("consume {}", name.lexeme)),
+        }
+    }
+
+    fn list(&mut self, exprs: &[Expr]) {
+        for (i, expr) in exprs.iter().enumerate() {
+            if i > 0 {
+                self.out.push_str(", ");
+            }
+            self.expression(expr);
+        }
+    }
+
+    // `a |> f |> g` is nested to the left, the stages are printed in order.
+    fn pipe(&mut self, expr: &Expr) {
+        let mut stages = vec![];
+        let mut current = expr;
+        while let Expr::Pipe {
+            left,
+            operator,
+            right,
+            ..
+        } = current
+        {
+            stages.push((operator.span.start, right.as_ref()));
+            current = left;
+        }
+        self.expression(current);
+        if let [(operator, stage)] = stages[..] {
+            self.inline_comments(operator);
+            if !self.out.ends_with(char::is_whitespace) {
+                self.out.push(' ');
+            }
+            self.out.push_str("|> ");
+            self.expression(stage);
+            return;
+        }
+
+        self.indent += 1;
+        for (operator, stage) in stages.into_iter().rev() {
+            self.stage_comments(operator);
+            self.out.push('\n');
+            self.start_line();
+            self.out.push_str("|> ");
+            self.expression(stage);
+        }
+        self.indent -= 1;
+    }
+
+    // Comments before the `|>` at `operator` end the line of the stage
+    // before it, or have lines of their own.
+    fn stage_comments(&mut self, operator: usize) {
+        let mut first = true;
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= operator {
+                break;
+            }
+            let text = comment.text.clone();
+            self.next_comment += 1;
+            if first {
+                self.out.push(' ');
+            } else {
+                self.out.push('\n');
+                self.start_line();
+            }
+            self.out.push_str(&text);
+            first = false;
+        }
+    }
+
+    fn arm(&mut self, arm: &MatchArm) {
+        self.pattern(&arm.pattern);
+        if let Some(guard) = &arm.guard {
+            self.out.push_str(" if ");
+            self.expression(guard);
+        }
+        self.out.push_str(" => ");
+        match arm.body.as_ref() {
+            Stmt::Block { statements, span } => self.block(statements, span.start, *span),
+            Stmt::Expression { expression, .. } => {
+                self.expression(expression);
+                self.out.push(',');
+            }
+            other => self.statement(other),
+        }
+    }
+
+    fn pattern(&mut self, pattern: &Pattern) {
+        match pattern {
+            Pattern::Literal { span, .. } => self.source_text(*span),
+            Pattern::Wildcard { .. } => self.out.push('_'),
+            Pattern::Binding { name, .. } => self.out.push_str(&name.lexeme),
+            Pattern::Typed {
+                pattern, type_expr, ..
+            } => {
+                self.pattern(pattern);
+                self.out.push_str(&format!(": {}", type_expr));
+            }
+            Pattern::Tuple { elements, .. } => {
+                self.out.push('(');
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.pattern(element);
+                }
+                if elements.len() == 1 {
+                    self.out.push(',');
+                }
+                self.out.push(')');
+            }
+            Pattern::Record { fields, .. } => {
+                self.out.push('{');
+                for (i, (name, pattern)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        self.out.push_str(", ");
+                    }
+                    self.out.push_str(&name.lexeme);
+                    match pattern {
+                        Pattern::Binding { name: binding, .. } if binding.lexeme == name.lexeme => {
+                        }
+                        pattern => {
+                            self.out.push_str(": ");
+                            self.pattern(pattern);
+                        }
+                    }
+                }
+                self.out.push('}');
+            }
+        }
+    }
+
+    // Comments on their own lines, before `offset`.
+    fn comments_before(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= offset {
+                break;
+            }
+            let comment = comment.clone();
+            self.next_comment += 1;
+            self.keep_blank_line(comment.span.line);
+            self.start_line();
+            self.out.push_str(&comment.text);
+            self.out.push('\n');
+            self.previous_line = Some(self.line_of(comment.span.end));
+        }
+    }
+
+    // Comments starting on the line the code before `end` ended on.
+    fn trailing_comments(&mut self, end: usize) {
+        let line = self.line_of(end);
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.line != line || comment.kind == TriviaKind::DocComment {
+                break;
+            }
+            self.out.push(' ');
+            self.out.push_str(&comment.text.clone());
+            self.next_comment += 1;
+        }
+        self.previous_line = Some(line);
+    }
+
+    // Comments ending the line of the `{` at `open`, before the code at
+    // `first` inside the braces.
+    fn comments_after_brace(&mut self, open: usize, first: usize) {
+        let line = self.line_of(open + 1);
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.line != line
+                || comment.span.start >= first
+                || comment.kind == TriviaKind::DocComment
+                || !self.ends_line(comment)
+            {
+                break;
+            }
+            self.out.push(' ');
+            self.out.push_str(&comment.text.clone());
+            self.next_comment += 1;
+        }
+    }
+
+    // Comments inside an expression or list, before `offset`, where they
+    // are. The code after a `//` comment goes on the next line, indented.
+    fn inline_comments(&mut self, offset: usize) {
+        while let Some(comment) = self.comments.get(self.next_comment) {
+            if comment.span.start >= offset {
+                break;
+            }
+            let comment = comment.clone();
+            self.next_comment += 1;
+            if !self
+                .out
+                .ends_with(|c: char| c.is_whitespace() || c == '(' || c == '{')
+            {
+                self.out.push(' ');
+            }
+            self.out.push_str(&comment.text);
+            if comment.kind != TriviaKind::BlockComment {
+                self.out.push('\n');
+                self.out.push_str(&INDENT.repeat(self.indent + 1));
+            }
+        }
+    }
+
+    // `inline_comments` before code, which a `/* */` comment is kept apart
+    // from.
+    fn code_comments(&mut self, offset: usize) {
+        self.inline_comments(offset);
+        if self.out.ends_with("*/") {
+            self.out.push(' ');
+        }
+    }
+
+    fn ends_line(&self, comment: &Trivia) -> bool {
+        self.source[comment.span.end..]
+            .split('\n')
+            .next()
+            .is_some_and(|rest| rest.trim().is_empty())
+    }
+
+    // The offset of the first `c` from `from` on that isn't in a comment.
+    fn find_code(&self, c: char, from: usize) -> usize {
+        let mut offset = from;
+        while let Some(found) = self.source[offset..].find(c).map(|i| offset + i) {
+            match self
+                .comments
+                .iter()
+                .find(|comment| comment.span.start <= found && found < comment.span.end)
+            {
+                Some(comment) => offset = comment.span.end,
+                None => return found,
+            }
+        }
+        self.source.len()
+    }
+
+    // One blank line where the source had at least one before `line`.
+    fn keep_blank_line(&mut self, line: usize) {
+        if let Some(previous) = self.previous_line {
+            if line > previous + 1 {
+                self.blank_line();
+            }
+        }
+    }
+
+    fn blank_line(&mut self) {
+        if !self.out.ends_with("\n\n") && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn start_line(&mut self) {
+        self.out.push_str(&INDENT.repeat(self.indent));
+    }
+
+    // Comments inside the text, e.g. in an interpolation, are part of it.
+    fn source_text(&mut self, span: Span) {
+        self.out.push_str(&self.source[span.start..span.end]);
+        while self
+            .comments
+            .get(self.next_comment)
+            .is_some_and(|comment| comment.span.start < span.end)
+        {
+            self.next_comment += 1;
+        }
+    }
+
+    // The line of the character before `end`, where a span ending there ends.
+    fn line_of(&self, end: usize) -> usize {
+        self.line_starts
+            .partition_point(|&start| start < end)
+            .max(1)
+    }
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    starts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(source: &str) -> String {
+        let formatted = format_source(source).unwrap();
+        assert_eq!(format_source(&formatted).unwrap(), formatted);
+        formatted
+    }
+
+    #[test]
+    fn formats_actors_and_functions() {
+        let source = "actor Main{let mut age=23;
+be update_age(by_how_much:(Int|String)){self.updating_age(by_how_much)}
+fn updating_age(by:Int)->Int{self.age=self.age+by;}}
+fn   empty( ) {   }
+let main=Main();main.update_age(0x1F);";
+        assert_eq!(
+            format(source),
+            "actor Main {
+    let mut age = 23;
+
+    be update_age(by_how_much: (Int | String)) {
+        self.updating_age(by_how_much)
+    }
+
+    fn updating_age(by: Int) -> Int {
+        self.age = self.age + by;
+    }
+}
+
+fn empty() {}
+
+let main = Main();
+main.update_age(0x1F);
+"
+        );
+    }
+
+    #[test]
+    fn formats_pipes_and_matches() {
+        let source = "let newly = by |> String.trim_string() |> String.parse_to_int();
+let one = x |> f;
+let s = match (n, \"a\\tb\") { (0, _) => \"zero ${n}\", (x: Int, y) if x > 1 => { print(y); x }
+{name, age: -1} => nil, _ => (1,) };";
+        assert_eq!(
+            format(source),
+            "let newly = by
+    |> String.trim_string()
+    |> String.parse_to_int();
+let one = x |> f;
+let s = match (n, \"a\\tb\") {
+    (0, _) => \"zero ${n}\",
+    (x: Int, y) if x > 1 => {
+        print(y);
+        x
+    }
+    {name, age: -1} => nil,
+    _ => (1,),
+};
+"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_and_blank_lines() {
+        let source = "// Leading comment.
+/// Says hello.
+fn hello(name) {   // trailing
+    /* before */ print(name);
+
+
+    // before the end
+}
+let a = 1;
+
+{ /* inside */ }
+// At the end.
+";
+        assert_eq!(
+            format(source),
+            "// Leading comment.
+/// Says hello.
+fn hello(name) { // trailing
+    /* before */
+    print(name);
+
+    // before the end
+}
+
+let a = 1;
+
+{
+    /* inside */
+}
+// At the end.
+"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_inside_expressions_in_place() {
+        let source = "let a = 1 + /* mid */ 2;
+let b = f(/* first */ x, y // last
+);
+let c = 1 + // one
+  2;
+let d = \"${a /* in */}\";";
+        assert_eq!(
+            format(source),
+            "let a = 1 + /* mid */ 2;
+let b = f(/* first */ x, y // last
+    );
+let c = 1 + // one
+    2;
+let d = \"${a /* in */}\";
+"
+        );
+
+        let source = "let p = a |> f // first
+  // more
+  |> g;
+let q = a // only
+  |> f;";
+        assert_eq!(
+            format(source),
+            "let p = a
+    |> f // first
+    // more
+    |> g;
+let q = a // only
+    |> f;
+"
+        );
+    }
+
+    #[test]
+    fn keeps_comments_inside_parameter_lists_in_place() {
+        let source = "fn g(
+    x: Int, // first
+    y: Int) -> /* sum */ Int { x + y }";
+        assert_eq!(
+            format(source),
+            "fn g(x: Int, // first
+    y: Int) -> /* sum */ Int {
+    x + y
+}
+"
+        );
+    }
+
+    #[test]
+    fn reports_syntax_errors() {
+        let errors = format_source("let a = ;").unwrap_err();
+        assert_eq!(errors[0].message, "Expected expression");
+    }
+}
@@ -0,0 +1,1564 @@
+use crate::environment::{AssignError, Environment};
+use crate::expr::{Expr, LiteralValue, MatchArm};
+use crate::overload::{self, OverloadError};
+use crate::pattern::Pattern;
+use crate::runtime::{Actor, ActorClass, Message, Output, Runtime};
+use crate::scanner::{Span, Token, TokenType};
+use crate::stdlib::{self, Module};
+use crate::stmt::{Function, FunctionKind, Stmt};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    String(String),
+    Bool(bool),
+    Nil,
+    Actor(Arc<Actor>),
+    ActorClass(Arc<ActorClass>),
+    Function(Closure),
+    NativeFunction(NativeFunction),
+    Module(Arc<Module>),
+    Tuple(Vec<Value>),
+    // Fields in the order they were written.
+    Record(Vec<(String, Value)>),
+}
+
+// A `fn` declared outside of an actor body, together with the scope it was
+// declared in and the actor `self` refers to inside of it. Overloads declared
+// in the same scope share one closure.
+#[derive(Clone)]
+pub struct Closure {
+    pub overloads: Vec<Arc<Function>>,
+    environment: Arc<Mutex<Environment>>,
+    actor: Option<Arc<Actor>>,
+}
+
+impl PartialEq for Closure {
+    fn eq(&self, other: &Self) -> bool {
+        self.overloads.len() == other.overloads.len()
+            && self
+                .overloads
+                .iter()
+                .zip(&other.overloads)
+                .all(|(a, b)| Arc::ptr_eq(a, b))
+            && Arc::ptr_eq(&self.environment, &other.environment)
+    }
+}
+
+impl Closure {
+    pub fn name(&self) -> &str {
+        &self.overloads[0].name.lexeme
+    }
+}
+
+impl fmt::Debug for Closure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Closure({})", self.name())
+    }
+}
+
+#[derive(Clone)]
+pub struct NativeFunction {
+    pub name: &'static str,
+    // Parameter and return types as they'd be written in an annotation, `_`
+    // for any type. Read by the checker.
+    pub params: &'static [&'static str],
+    pub returns: &'static str,
+    pub function: fn(&Interpreter, &[Value]) -> Result<Value, String>,
+}
+
+impl PartialEq for NativeFunction {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "NativeFunction({})", self.name)
+    }
+}
+
+impl Value {
+    fn from_literal(literal: &LiteralValue) -> Self {
+        match literal {
+            LiteralValue::Int(x) => Value::Int(*x),
+            LiteralValue::Float(x) => Value::Float(*x),
+            LiteralValue::StringValue(s) => Value::String(s.clone()),
+            LiteralValue::True => Value::Bool(true),
+            LiteralValue::False => Value::Bool(false),
+            LiteralValue::Nil => Value::Nil,
+        }
+    }
+
+    // Ints are converted when mixed with Floats.
+    fn as_float(&self) -> Option<f64> {
+        match self {
+            Value::Int(x) => Some(*x as f64),
+            Value::Float(x) => Some(*x),
+            _ => None,
+        }
+    }
+
+    // `nil` and `false` are falsy, everything else is truthy.
+    fn is_truthy(&self) -> bool {
+        !matches!(self, Value::Nil | Value::Bool(false))
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_) => "int",
+            Value::Float(_) => "float",
+            Value::String(_) => "string",
+            Value::Bool(_) => "bool",
+            Value::Nil => "nil",
+            Value::Actor(_) => "actor",
+            Value::ActorClass(_) => "actor class",
+            Value::Function(_) | Value::NativeFunction(_) => "function",
+            Value::Module(_) => "module",
+            Value::Tuple(_) => "tuple",
+            Value::Record(_) => "record",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(x) => write!(f, "{}", x),
+            Value::Float(x) => write!(f, "{:?}", x),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Nil => write!(f, "nil"),
+            Value::Actor(actor) => write!(f, "<actor {}#{}>", actor.class.name.lexeme, actor.id),
+            Value::ActorClass(class) => write!(f, "<actor {}>", class.name.lexeme),
+            Value::Function(closure) => write!(f, "<fn {}>", closure.name()),
+            Value::NativeFunction(function) => write!(f, "<native fn {}>", function.name),
+            Value::Module(module) => write!(f, "<module {}>", module.name),
+            Value::Tuple(values) if values.len() == 1 => write!(f, "({},)", values[0]),
+            Value::Tuple(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "({})", values.join(", "))
+            }
+            Value::Record(fields) => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Span,
+    pub note: Option<String>,
+}
+
+impl RuntimeError {
+    fn new(token: &Token, message: String) -> Self {
+        Self {
+            message,
+            span: token.span,
+            note: None,
+        }
+    }
+
+    fn with_note(mut self, note: &str) -> Self {
+        self.note = Some(note.to_string());
+        self
+    }
+
+    fn overload(token: &Token, err: OverloadError) -> Self {
+        Self {
+            message: err.message,
+            span: token.span,
+            note: err.note,
+        }
+    }
+}
+
+// Why statement execution stopped before the end of a block.
+enum Unwind {
+    Error(RuntimeError),
+    Return(Value),
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(err: RuntimeError) -> Self {
+        Unwind::Error(err)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Runtime error: {}", self.span, self.message)
+    }
+}
+
+pub struct Interpreter {
+    environment: Arc<Mutex<Environment>>,
+    runtime: Arc<Runtime>,
+    // The actor whose method is running, `None` for top-level code.
+    actor: Option<Arc<Actor>>,
+    // Only the interpreter that created the runtime shuts it down, not the
+    // ones running behaviours on worker threads.
+    owns_runtime: bool,
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        Self::with_output(Arc::new(Mutex::new(io::stdout())))
+    }
+
+    pub fn with_output(output: Output) -> Self {
+        let globals = Arc::new(Mutex::new(Environment::new()));
+        for (name, value) in stdlib::globals() {
+            globals.lock().unwrap().define(name, value, false);
+        }
+        Self {
+            environment: globals.clone(),
+            runtime: Runtime::new(globals, output),
+            actor: None,
+            owns_runtime: true,
+        }
+    }
+
+    pub fn for_actor(runtime: Arc<Runtime>) -> Self {
+        Self {
+            environment: runtime.globals.clone(),
+            runtime,
+            actor: None,
+            owns_runtime: false,
+        }
+    }
+
+    // Where `print` writes to.
+    pub fn output(&self) -> Output {
+        self.runtime.output.clone()
+    }
+
+    // Returns the value of the last statement, `nil` if it was not an
+    // expression. Messages sent to actors are all processed before returning,
+    // even after an error: the error that stopped the statements comes first,
+    // then those of every failed behaviour.
+    pub fn interpret(&mut self, statements: &[Stmt]) -> Result<Value, Vec<RuntimeError>> {
+        // The parser rejects `return` outside of functions.
+        let result = match self.execute_all(statements) {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+        };
+        let mut errors = self.runtime.wait();
+        match result {
+            Ok(last) if errors.is_empty() => Ok(last),
+            Ok(_) => Err(errors),
+            Err(err) => {
+                errors.insert(0, err);
+                Err(errors)
+            }
+        }
+    }
+
+    fn execute_all(&mut self, statements: &[Stmt]) -> Result<Value, Unwind> {
+        let mut last = Value::Nil;
+        for statement in statements {
+            last = self.execute(statement)?;
+        }
+        Ok(last)
+    }
+
+    pub fn run_behaviour(
+        &mut self,
+        actor: &Arc<Actor>,
+        message: Message,
+    ) -> Result<Value, RuntimeError> {
+        // The sender already resolved the overload, resolving it again from
+        // the same arguments picks the same behaviour.
+        let class = actor.class.clone();
+        let behaviours = class.methods(&message.behaviour);
+        if behaviours.is_empty() {
+            return Err(RuntimeError::new(
+                &class.name,
+                format!(
+                    "Actor '{}' has no behaviour '{}'",
+                    class.name.lexeme, message.behaviour
+                ),
+            ));
+        }
+        let behaviour = overload::resolve(&behaviours, &message.arguments)
+            .map_err(|err| RuntimeError::overload(&class.name, err))?;
+        self.invoke(actor, behaviour, message.arguments)
+    }
+
+    // Runs a method of `actor` synchronously on the current thread.
+    fn invoke(
+        &mut self,
+        actor: &Arc<Actor>,
+        function: &Function,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let globals = self.runtime.globals.clone();
+        self.call_function(function, globals, Some(actor.clone()), arguments)
+    }
+
+    // Without a `return` a function evaluates to its last statement, like a
+    // block does.
+    fn call_function(
+        &mut self,
+        function: &Function,
+        enclosing: Arc<Mutex<Environment>>,
+        actor: Option<Arc<Actor>>,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let mut environment = Environment::with_enclosing(enclosing);
+        for (param, argument) in function.params.iter().zip(arguments) {
+            environment.define(&param.name.lexeme, argument, false);
+        }
+
+        let previous = std::mem::replace(&mut self.actor, actor);
+        let result = self.execute_block(&function.body, environment);
+        self.actor = previous;
+        match result {
+            Ok(value) | Err(Unwind::Return(value)) => Ok(value),
+            Err(Unwind::Error(err)) => Err(err),
+        }
+    }
+
+    // Field initializers run in the global scope, outside of any actor.
+    fn spawn(&mut self, class: Arc<ActorClass>) -> Result<Value, RuntimeError> {
+        let environment = Environment::with_enclosing(self.runtime.globals.clone());
+        let previous = std::mem::replace(&mut self.environment, Arc::new(Mutex::new(environment)));
+        let previous_actor = self.actor.take();
+
+        let mut fields = HashMap::new();
+        let mut result = Ok(());
+        for field in &class.fields {
+            if let Stmt::Let {
+                name, initializer, ..
+            } = field
+            {
+                let value = match initializer {
+                    // The parser rejects `return` outside of functions.
+                    Some(expr) => match self.evaluate(expr) {
+                        Ok(value) | Err(Unwind::Return(value)) => value,
+                        Err(Unwind::Error(err)) => {
+                            result = Err(err);
+                            break;
+                        }
+                    },
+                    None => Value::Nil,
+                };
+                fields.insert(name.lexeme.clone(), value);
+            }
+        }
+
+        self.environment = previous;
+        self.actor = previous_actor;
+        result?;
+        Ok(Value::Actor(self.runtime.spawn(class, fields)))
+    }
+
+    fn execute(&mut self, stmt: &Stmt) -> Result<Value, Unwind> {
+        match stmt {
+            Stmt::Expression { expression, .. } => self.evaluate(expression),
+            Stmt::Let {
+                name,
+                mutable,
+                initializer,
+                ..
+            } => {
+                let value = match initializer {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                self.environment
+                    .lock()
+                    .unwrap()
+                    .define(&name.lexeme, value, *mutable);
+                Ok(Value::Nil)
+            }
+            Stmt::Block { statements, .. } => {
+                let environment = Environment::with_enclosing(self.environment.clone());
+                self.execute_block(statements, environment)
+            }
+            Stmt::Actor {
+                name,
+                fields,
+                methods,
+                ..
+            } => {
+                let class = ActorClass {
+                    name: name.clone(),
+                    fields: fields.clone(),
+                    methods: methods.clone(),
+                };
+                self.environment.lock().unwrap().define(
+                    &name.lexeme,
+                    Value::ActorClass(Arc::new(class)),
+                    false,
+                );
+                Ok(Value::Nil)
+            }
+            // A `fn` declared next to another one of the same name joins its
+            // overloads, replacing any with the same parameter types.
+            Stmt::Function(function) => {
+                let mut environment = self.environment.lock().unwrap();
+                let mut overloads = match environment.get_local(&function.name.lexeme) {
+                    Some(Value::Function(closure)) => closure.overloads,
+                    _ => vec![],
+                };
+                overloads.retain(|overload| !overload::same_signature(overload, function));
+                overloads.push(Arc::new(function.clone()));
+
+                let closure = Closure {
+                    overloads,
+                    environment: self.environment.clone(),
+                    actor: self.actor.clone(),
+                };
+                environment.define(&function.name.lexeme, Value::Function(closure), false);
+                Ok(Value::Nil)
+            }
+            Stmt::Return { value, .. } => {
+                let value = match value {
+                    Some(expr) => self.evaluate(expr)?,
+                    None => Value::Nil,
+                };
+                Err(Unwind::Return(value))
+            }
+        }
+    }
+
+    fn execute_block(
+        &mut self,
+        statements: &[Stmt],
+        environment: Environment,
+    ) -> Result<Value, Unwind> {
+        let previous = std::mem::replace(&mut self.environment, Arc::new(Mutex::new(environment)));
+        let result = self.execute_all(statements);
+        self.environment = previous;
+        result
+    }
+
+    // A `return` in a match arm unwinds through the expressions around it.
+    fn evaluate(&mut self, expr: &Expr) -> Result<Value, Unwind> {
+        match expr {
+            Expr::Literal { value, .. } => Ok(Value::from_literal(value)),
+            Expr::Grouping { expression, .. } => self.evaluate(expression),
+            Expr::Unary {
+                operator, right, ..
+            } => {
+                let right = self.evaluate(right)?;
+                Ok(self.unary(operator, right)?)
+            }
+            Expr::Binary {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let left = self.evaluate(left)?;
+                let right = self.evaluate(right)?;
+                Ok(self.binary(left, operator, right)?)
+            }
+            // The checker makes sure a consumed local isn't read again.
+            Expr::Variable { name, .. } | Expr::Consume { name, .. } => {
+                match self.environment.lock().unwrap().get(&name.lexeme) {
+                    Some(value) => Ok(value),
+                    None => Err(RuntimeError::new(
+                        name,
+                        format!("Undefined variable '{}'", name.lexeme),
+                    )
+                    .into()),
+                }
+            }
+            Expr::Assign { name, value, .. } => {
+                let value = self.evaluate(value)?;
+                match self
+                    .environment
+                    .lock()
+                    .unwrap()
+                    .assign(&name.lexeme, value.clone())
+                {
+                    Ok(()) => Ok(value),
+                    Err(AssignError::Undefined) => Err(RuntimeError::new(
+                        name,
+                        format!("Undefined variable '{}'", name.lexeme),
+                    )
+                    .into()),
+                    Err(AssignError::Immutable) => Err(RuntimeError::new(
+                        name,
+                        format!(
+                            "Cannot assign twice to immutable variable '{}'",
+                            name.lexeme
+                        ),
+                    )
+                    .with_note(&format!(
+                        "declare it with 'let mut {}' to make it mutable",
+                        name.lexeme
+                    ))
+                    .into()),
+                }
+            }
+            Expr::Call {
+                callee,
+                paren,
+                arguments,
+                ..
+            } => {
+                if let Expr::Get { object, name, .. } = callee.as_ref() {
+                    let object = self.evaluate(object)?;
+                    let arguments = self.evaluate_arguments(arguments)?;
+                    return Ok(self.call_method(object, name, arguments)?);
+                }
+
+                let callee = self.evaluate(callee)?;
+                let arguments = self.evaluate_arguments(arguments)?;
+                Ok(self.call(callee, paren, arguments)?)
+            }
+            // `value |> f(a)` calls `f(value, a)`, `value |> f` calls `f(value)`.
+            Expr::Pipe {
+                left,
+                operator,
+                right,
+                ..
+            } => {
+                let value = self.evaluate(left)?;
+                let (callee, paren, arguments) = match right.as_ref() {
+                    Expr::Call {
+                        callee,
+                        paren,
+                        arguments,
+                        ..
+                    } => (callee.as_ref(), paren, arguments.as_slice()),
+                    other => (other, operator, &[][..]),
+                };
+
+                let mut values = vec![value];
+                if let Expr::Get { object, name, .. } = callee {
+                    let object = self.evaluate(object)?;
+                    values.extend(self.evaluate_arguments(arguments)?);
+                    return Ok(self.call_method(object, name, values)?);
+                }
+                let callee = self.evaluate(callee)?;
+                values.extend(self.evaluate_arguments(arguments)?);
+                Ok(self.call(callee, paren, values)?)
+            }
+            Expr::Get { object, name, .. } => {
+                let object = match self.evaluate(object)? {
+                    Value::Module(module) => return Ok(self.module_function(&module, name)?),
+                    Value::Record(fields) => return Ok(record_field(fields, name)?),
+                    object => object,
+                };
+                let actor = self.own_actor(object, name)?;
+                match actor.field(&name.lexeme) {
+                    Some(value) => Ok(value),
+                    None if !actor.class.methods(&name.lexeme).is_empty() => Err(
+                        RuntimeError::new(name, format!("Method '{}' must be called", name.lexeme))
+                            .into(),
+                    ),
+                    None => Err(RuntimeError::new(
+                        name,
+                        format!(
+                            "Actor '{}' has no field '{}'",
+                            actor.class.name.lexeme, name.lexeme
+                        ),
+                    )
+                    .into()),
+                }
+            }
+            Expr::Set {
+                object,
+                name,
+                value,
+                ..
+            } => {
+                let object = self.evaluate(object)?;
+                let actor = self.own_actor(object, name)?;
+                match actor.class.field_is_mutable(&name.lexeme) {
+                    Some(true) => (),
+                    Some(false) => {
+                        return Err(RuntimeError::new(
+                            name,
+                            format!("Cannot assign twice to immutable field '{}'", name.lexeme),
+                        )
+                        .with_note(&format!(
+                            "declare it with 'let mut {}' to make it mutable",
+                            name.lexeme
+                        ))
+                        .into())
+                    }
+                    None => {
+                        return Err(RuntimeError::new(
+                            name,
+                            format!(
+                                "Actor '{}' has no field '{}'",
+                                actor.class.name.lexeme, name.lexeme
+                            ),
+                        )
+                        .into())
+                    }
+                }
+                let value = self.evaluate(value)?;
+                actor.set_field(&name.lexeme, value.clone());
+                Ok(value)
+            }
+            Expr::SelfRef { keyword, .. } => match &self.actor {
+                Some(actor) => Ok(Value::Actor(actor.clone())),
+                None => Err(RuntimeError::new(
+                    keyword,
+                    "Cannot use 'self' outside of an actor".to_string(),
+                )
+                .into()),
+            },
+            Expr::Tuple { elements, .. } => Ok(Value::Tuple(self.evaluate_arguments(elements)?)),
+            Expr::Record { fields, .. } => {
+                let mut values = Vec::with_capacity(fields.len());
+                for (name, value) in fields {
+                    values.push((name.lexeme.clone(), self.evaluate(value)?));
+                }
+                Ok(Value::Record(values))
+            }
+            Expr::Match {
+                keyword,
+                value,
+                arms,
+                ..
+            } => {
+                let value = self.evaluate(value)?;
+                self.match_arms(keyword, value, arms)
+            }
+        }
+    }
+
+    // Runs the first arm whose pattern matches and whose guard holds, with the
+    // names its pattern binds in a scope of their own.
+    fn match_arms(
+        &mut self,
+        keyword: &Token,
+        value: Value,
+        arms: &[MatchArm],
+    ) -> Result<Value, Unwind> {
+        for arm in arms {
+            let mut bindings = vec![];
+            if !match_pattern(&arm.pattern, &value, &mut bindings) {
+                continue;
+            }
+            let mut environment = Environment::with_enclosing(self.environment.clone());
+            for (name, value) in bindings {
+                environment.define(&name, value, false);
+            }
+            let environment = Arc::new(Mutex::new(environment));
+            let previous = std::mem::replace(&mut self.environment, environment);
+            let result = self.run_arm(arm);
+            self.environment = previous;
+            if let Some(result) = result.transpose() {
+                return result;
+            }
+        }
+        Err(RuntimeError::new(
+            keyword,
+            format!("No match arm accepts {}", overload::type_of(&value)),
+        )
+        .with_note(&format!("the value was {}", value))
+        .into())
+    }
+
+    // `None` if the guard rejected the arm.
+    fn run_arm(&mut self, arm: &MatchArm) -> Result<Option<Value>, Unwind> {
+        if let Some(guard) = &arm.guard {
+            if !self.evaluate(guard)?.is_truthy() {
+                return Ok(None);
+            }
+        }
+        match arm.body.as_ref() {
+            Stmt::Block { statements, .. } => {
+                let environment = Environment::with_enclosing(self.environment.clone());
+                self.execute_block(statements, environment).map(Some)
+            }
+            body => self.execute(body).map(Some),
+        }
+    }
+
+    fn call(
+        &mut self,
+        callee: Value,
+        paren: &Token,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        match callee {
+            Value::Function(closure) => {
+                let overloads: Vec<&Function> =
+                    closure.overloads.iter().map(|f| f.as_ref()).collect();
+                let function = overload::resolve(&overloads, &arguments)
+                    .map_err(|err| RuntimeError::overload(paren, err))?;
+                self.call_function(
+                    function,
+                    closure.environment.clone(),
+                    closure.actor.clone(),
+                    arguments,
+                )
+            }
+            Value::NativeFunction(native) => {
+                self.check_arity(paren, native.params.len(), arguments.len())?;
+                (native.function)(self, &arguments)
+                    .map_err(|message| RuntimeError::new(paren, message))
+            }
+            Value::ActorClass(class) => {
+                self.check_arity(paren, 0, arguments.len())?;
+                self.spawn(class)
+            }
+            other => Err(RuntimeError::new(
+                paren,
+                format!(
+                    "Can only call functions and actors, got {}",
+                    other.type_name()
+                ),
+            )),
+        }
+    }
+
+    fn evaluate_arguments(&mut self, arguments: &[Expr]) -> Result<Vec<Value>, Unwind> {
+        arguments.iter().map(|arg| self.evaluate(arg)).collect()
+    }
+
+    fn check_arity(&self, paren: &Token, arity: usize, got: usize) -> Result<(), RuntimeError> {
+        if arity == got {
+            Ok(())
+        } else {
+            Err(RuntimeError::new(
+                paren,
+                format!("Expected {} arguments but got {}", arity, got),
+            ))
+        }
+    }
+
+    // Behaviours are queued in the actor's mailbox and return `nil` right
+    // away; `fn` methods run synchronously and only on `self`.
+    fn call_method(
+        &mut self,
+        object: Value,
+        name: &Token,
+        arguments: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let actor = match object {
+            Value::Actor(actor) => actor,
+            Value::Module(module) => {
+                let function = self.module_function(&module, name)?;
+                return self.call(function, name, arguments);
+            }
+            other => {
+                return Err(RuntimeError::new(
+                    name,
+                    format!("Only actors have methods, got {}", other.type_name()),
+                ))
+            }
+        };
+        let class = actor.class.clone();
+        let methods = class.methods(&name.lexeme);
+        if methods.is_empty() {
+            return Err(RuntimeError::new(
+                name,
+                format!(
+                    "Actor '{}' has no method '{}'",
+                    class.name.lexeme, name.lexeme
+                ),
+            ));
+        }
+        let method = overload::resolve(&methods, &arguments)
+            .map_err(|err| RuntimeError::overload(name, err))?;
+
+        match method.kind {
+            FunctionKind::Behaviour => {
+                if let Some((closure, owner)) = arguments
+                    .iter()
+                    .find_map(|argument| unsendable_closure(argument, &actor))
+                {
+                    return Err(RuntimeError::new(
+                        name,
+                        format!(
+                            "Cannot send fn '{}' to another actor, it was declared in actor '{}'",
+                            closure.name(),
+                            owner.class.name.lexeme
+                        ),
+                    )
+                    .with_note("it would reach 'self' and the locals of the actor that declared it from another thread"));
+                }
+                let message = Message {
+                    behaviour: name.lexeme.clone(),
+                    arguments,
+                };
+                self.runtime.send(&actor, message);
+                Ok(Value::Nil)
+            }
+            FunctionKind::Function if self.actor.as_ref() == Some(&actor) => {
+                self.invoke(&actor, method, arguments)
+            }
+            FunctionKind::Function => Err(RuntimeError::new(
+                name,
+                format!(
+                    "Cannot call fn '{}' of another actor, only its behaviours",
+                    name.lexeme
+                ),
+            )
+            .with_note("declare it with 'be' to make it callable from outside the actor")),
+        }
+    }
+
+    fn module_function(&self, module: &Module, name: &Token) -> Result<Value, RuntimeError> {
+        match module.function(&name.lexeme) {
+            Some(function) => Ok(Value::NativeFunction(function.clone())),
+            None => Err(RuntimeError::new(
+                name,
+                format!("Module '{}' has no function '{}'", module.name, name.lexeme),
+            )),
+        }
+    }
+
+    // Fields are private to their actor, only `self` can read or write them.
+    fn own_actor(&self, object: Value, name: &Token) -> Result<Arc<Actor>, RuntimeError> {
+        match object {
+            Value::Actor(actor) if self.actor.as_ref() == Some(&actor) => Ok(actor),
+            Value::Actor(actor) => Err(RuntimeError::new(
+                name,
+                format!(
+                    "Cannot access field '{}' of another actor '{}'",
+                    name.lexeme, actor.class.name.lexeme
+                ),
+            )
+            .with_note("fields are private to their actor, send it a behaviour instead")),
+            other => Err(RuntimeError::new(
+                name,
+                format!("Only actors have fields, got {}", other.type_name()),
+            )),
+        }
+    }
+
+    fn unary(&self, operator: &Token, right: Value) -> Result<Value, RuntimeError> {
+        match (&operator.token_type, right) {
+            (TokenType::Minus, Value::Int(x)) => match x.checked_neg() {
+                Some(x) => Ok(Value::Int(x)),
+                None => Err(RuntimeError::new(
+                    operator,
+                    format!("Integer overflow: -({}) does not fit in an Int", x),
+                )),
+            },
+            (TokenType::Minus, Value::Float(x)) => Ok(Value::Float(-x)),
+            (TokenType::Minus, other) => Err(RuntimeError::new(
+                operator,
+                format!("Operand of '-' must be a number, got {}", other.type_name()),
+            )),
+            (TokenType::Bang, other) => Ok(Value::Bool(!other.is_truthy())),
+            (typ, _) => Err(RuntimeError::new(
+                operator,
+                format!("Unknown unary operator {}", typ),
+            )),
+        }
+    }
+
+    fn binary(&self, left: Value, operator: &Token, right: Value) -> Result<Value, RuntimeError> {
+        use Value::*;
+
+        let numeric = match (&left, &right) {
+            (Int(x), Int(y)) => self.int_binary(*x, operator, *y)?,
+            _ => match (left.as_float(), right.as_float()) {
+                (Some(x), Some(y)) => self.float_binary(x, operator, y)?,
+                _ => None,
+            },
+        };
+        if let Some(result) = numeric {
+            return Ok(result);
+        }
+
+        match (left, &operator.token_type, right) {
+            (String(x), TokenType::Plus, String(y)) => Ok(String(x + &y)),
+            (x, TokenType::EqualEqual, y) => Ok(Bool(x == y)),
+            (x, TokenType::BangEqual, y) => Ok(Bool(x != y)),
+            (x, _, y) => Err(RuntimeError::new(
+                operator,
+                format!(
+                    "Operator '{}' cannot be applied to {} and {}",
+                    operator.lexeme,
+                    x.type_name(),
+                    y.type_name()
+                ),
+            )),
+        }
+    }
+}
+
+fn record_field(fields: Vec<(String, Value)>, name: &Token) -> Result<Value, RuntimeError> {
+    match fields.into_iter().find(|(field, _)| *field == name.lexeme) {
+        Some((_, value)) => Ok(value),
+        None => Err(RuntimeError::new(
+            name,
+            format!("Record has no field '{}'", name.lexeme),
+        )),
+    }
+}
+
+// Collects the names the pattern binds into `bindings` when it matches.
+fn match_pattern(pattern: &Pattern, value: &Value, bindings: &mut Vec<(String, Value)>) -> bool {
+    match (pattern, value) {
+        (Pattern::Literal { value: literal, .. }, value) => Value::from_literal(literal) == *value,
+        (Pattern::Wildcard { .. }, _) => true,
+        (Pattern::Binding { name, .. }, value) => {
+            bindings.push((name.lexeme.clone(), value.clone()));
+            true
+        }
+        (
+            Pattern::Typed {
+                pattern, type_expr, ..
+            },
+            value,
+        ) => overload::has_type(value, type_expr) && match_pattern(pattern, value, bindings),
+        (Pattern::Tuple { elements, .. }, Value::Tuple(values)) => {
+            elements.len() == values.len()
+                && elements
+                    .iter()
+                    .zip(values)
+                    .all(|(pattern, value)| match_pattern(pattern, value, bindings))
+        }
+        (Pattern::Record { fields, .. }, Value::Record(values)) => {
+            fields.iter().all(|(name, pattern)| {
+                values
+                    .iter()
+                    .find(|(field, _)| *field == name.lexeme)
+                    .is_some_and(|(_, value)| match_pattern(pattern, value, bindings))
+            })
+        }
+        (Pattern::Tuple { .. } | Pattern::Record { .. }, _) => false,
+    }
+}
+
+impl Interpreter {
+    // Overflowing Int arithmetic is an error, division truncates toward zero.
+    fn int_binary(&self, x: i64, operator: &Token, y: i64) -> Result<Option<Value>, RuntimeError> {
+        let result = match operator.token_type {
+            TokenType::Slash if y == 0 => {
+                return Err(RuntimeError::new(operator, "Division by zero".to_string()))
+            }
+            TokenType::Plus => x.checked_add(y),
+            TokenType::Minus => x.checked_sub(y),
+            TokenType::Star => x.checked_mul(y),
+            TokenType::Slash => x.checked_div(y),
+            TokenType::Greater => return Ok(Some(Value::Bool(x > y))),
+            TokenType::GreaterEqual => return Ok(Some(Value::Bool(x >= y))),
+            TokenType::Less => return Ok(Some(Value::Bool(x < y))),
+            TokenType::LessEqual => return Ok(Some(Value::Bool(x <= y))),
+            _ => return Ok(None),
+        };
+        match result {
+            Some(result) => Ok(Some(Value::Int(result))),
+            None => Err(RuntimeError::new(
+                operator,
+                format!(
+                    "Integer overflow: {} {} {} does not fit in an Int",
+                    x, operator.lexeme, y
+                ),
+            )
+            .with_note("use Floats for numbers this large")),
+        }
+    }
+
+    // At least one of the operands is a Float, the other is converted.
+    fn float_binary(
+        &self,
+        x: f64,
+        operator: &Token,
+        y: f64,
+    ) -> Result<Option<Value>, RuntimeError> {
+        let result = match operator.token_type {
+            TokenType::Plus => Value::Float(x + y),
+            TokenType::Minus => Value::Float(x - y),
+            TokenType::Star => Value::Float(x * y),
+            TokenType::Slash if y == 0.0 => {
+                return Err(RuntimeError::new(operator, "Division by zero".to_string()))
+            }
+            TokenType::Slash => Value::Float(x / y),
+            TokenType::Greater => Value::Bool(x > y),
+            TokenType::GreaterEqual => Value::Bool(x >= y),
+            TokenType::Less => Value::Bool(x < y),
+            TokenType::LessEqual => Value::Bool(x <= y),
+            TokenType::EqualEqual => Value::Bool(x == y),
+            TokenType::BangEqual => Value::Bool(x != y),
+            _ => return Ok(None),
+        };
+        Ok(Some(result))
+    }
+}
+
+impl Drop for Interpreter {
+    fn drop(&mut self) {
+        if self.owns_runtime {
+            self.runtime.shutdown();
+        }
+    }
+}
+
+// A closure declared in an actor sees `self` and the locals of the method
+// it was declared in, it can only be sent back to that actor.
+fn unsendable_closure<'a>(
+    value: &'a Value,
+    to: &Arc<Actor>,
+) -> Option<(&'a Closure, &'a Arc<Actor>)> {
+    match value {
+        Value::Function(closure) => match &closure.actor {
+            Some(owner) if owner != to => Some((closure, owner)),
+            _ => None,
+        },
+        Value::Tuple(values) => values
+            .iter()
+            .find_map(|value| unsendable_closure(value, to)),
+        Value::Record(fields) => fields
+            .iter()
+            .find_map(|(_, value)| unsendable_closure(value, to)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+
+    fn eval(source: &str) -> Result<Value, RuntimeError> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+        Interpreter::new()
+            .interpret(&statements)
+            .map_err(|mut errors| errors.remove(0))
+    }
+
+    // Runs `source` and returns what it printed.
+    fn run(source: &str) -> Result<String, RuntimeError> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().unwrap();
+        let mut parser = Parser::new(tokens);
+        let statements = parser.parse().unwrap();
+
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        Interpreter::with_output(output.clone())
+            .interpret(&statements)
+            .map_err(|mut errors| errors.remove(0))?;
+        let bytes = output.lock().unwrap().clone();
+        Ok(String::from_utf8(bytes).unwrap())
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert_eq!(eval("1 + 2 * 3").unwrap(), Value::Int(7));
+        assert_eq!(eval("(1 + 2) * 3").unwrap(), Value::Int(9));
+        assert_eq!(eval("-(4 - 6) / 2").unwrap(), Value::Int(1));
+    }
+
+    #[test]
+    fn ints_and_floats_are_distinct() {
+        assert_eq!(eval("7 / 2").unwrap(), Value::Int(3));
+        assert_eq!(eval("-7 / 2").unwrap(), Value::Int(-3));
+        assert_eq!(eval("7.0 / 2").unwrap(), Value::Float(3.5));
+        assert_eq!(eval("1 + 0.5").unwrap(), Value::Float(1.5));
+        assert_eq!(eval("1 == 1.0").unwrap(), Value::Bool(true));
+        assert_eq!(eval("2 > 1.5").unwrap(), Value::Bool(true));
+        assert_eq!(eval("2.0").unwrap().to_string(), "2.0");
+        assert_eq!(eval("2").unwrap().to_string(), "2");
+        assert_eq!(
+            eval("9007199254740993 == 9007199254740992").unwrap(),
+            Value::Bool(false)
+        );
+    }
+
+    #[test]
+    fn int_overflow_and_division_by_zero_are_errors() {
+        let err = eval("9223372036854775807 + 1").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Integer overflow: 9223372036854775807 + 1 does not fit in an Int"
+        );
+        assert_eq!(
+            err.note.as_deref(),
+            Some("use Floats for numbers this large")
+        );
+        assert_eq!(eval("-9223372036854775808").unwrap(), Value::Int(i64::MIN));
+        let err = eval("let min = -9223372036854775808; -min").unwrap_err();
+        assert_eq!(
+            err.message,
+            "Integer overflow: -(-9223372036854775808) does not fit in an Int"
+        );
+        assert_eq!(eval("1 / 0").unwrap_err().message, "Division by zero");
+        assert_eq!(eval("1.5 / 0").unwrap_err().message, "Division by zero");
+    }
+
+    #[test]
+    fn evaluate_comparison_and_equality() {
+        assert_eq!(eval("1 < 2").unwrap(), Value::Bool(true));
+        assert_eq!(eval("3 >= 4").unwrap(), Value::Bool(false));
+        assert_eq!(eval("1 == 1").unwrap(), Value::Bool(true));
+        assert_eq!(eval("\"a\" != \"a\"").unwrap(), Value::Bool(false));
+        assert_eq!(eval("!nil").unwrap(), Value::Bool(true));
+        assert_eq!(eval("!true == false").unwrap(), Value::Bool(true));
+    }
+
+    #[test]
+    fn evaluate_string_concatenation() {
+        assert_eq!(
+            eval("\"lok\" + \"lang\"").unwrap(),
+            Value::String("loklang".to_string())
+        );
+    }
+
+    #[test]
+    fn runtime_error_carries_operator_line() {
+        let err = eval("1 +\n\"a\"").unwrap_err();
+        assert_eq!(err.span, Span::new(1, 3, 2, 3));
+        assert_eq!(
+            err.message,
+            "Operator '+' cannot be applied to int and string"
+        );
+
+        let err = eval("1 /\n\n0").unwrap_err();
+        assert_eq!(err.message, "Division by zero");
+    }
+
+    #[test]
+    fn let_bindings_and_blocks() {
+        assert_eq!(eval("let a = 1; let b = 2; a + b").unwrap(), Value::Int(3));
+        assert_eq!(
+            eval("let mut age = 23; { age = age + 1; } age").unwrap(),
+            Value::Int(24)
+        );
+        assert_eq!(eval("let a = 1; { let a = 2; } a").unwrap(), Value::Int(1));
+        assert_eq!(eval("let a; a").unwrap(), Value::Nil);
+    }
+
+    #[test]
+    fn assigning_immutable_binding_is_an_error() {
+        let err = eval("let age = 23;\nage = 24;").unwrap_err();
+        assert_eq!(err.span, Span::new(2, 1, 14, 17));
+        assert_eq!(
+            err.message,
+            "Cannot assign twice to immutable variable 'age'"
+        );
+        assert_eq!(
+            err.note,
+            Some("declare it with 'let mut age' to make it mutable".to_string())
+        );
+    }
+
+    #[test]
+    fn undefined_variable_is_an_error() {
+        let err = eval("{ let a = 1; } a").unwrap_err();
+        assert_eq!(err.message, "Undefined variable 'a'");
+    }
+
+    #[test]
+    fn negating_a_string_is_an_error() {
+        let err = eval("-\"abc\"").unwrap_err();
+        assert_eq!(err.message, "Operand of '-' must be a number, got string");
+    }
+
+    #[test]
+    fn behaviours_process_one_message_at_a_time() {
+        let source = "
+            actor Counter {
+                let mut count = 0;
+                be increment() { self.count = self.count + 1; }
+                be report() { print(self.count); }
+            }
+            let counter = Counter();
+            counter.increment(); counter.increment(); counter.increment();
+            counter.increment(); counter.increment();
+            counter.report();
+        ";
+        assert_eq!(run(source).unwrap(), "5\n");
+    }
+
+    #[test]
+    fn messages_from_one_sender_arrive_in_order() {
+        let source = "
+            actor Log {
+                be log(line) { print(line); }
+            }
+            actor Relay {
+                be forward(log, line) { log.log(line); }
+            }
+            let log = Log();
+            let relay = Relay();
+            log.log(\"first\");
+            relay.forward(log, \"second\");
+        ";
+        // `first` is in the mailbox before `forward` is even sent, so it is
+        // printed before anything the relay sends.
+        assert_eq!(run(source).unwrap(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn many_actors_run_concurrently() {
+        let mut source = String::from(
+            "actor Total {
+                let mut sum = 0;
+                be add(n) { self.sum = self.sum + n; }
+                be report() { print(self.sum); }
+            }
+            actor Worker {
+                be work(total, n) { total.add(n); }
+            }
+            let total = Total();
+            ",
+        );
+        for n in 1..=50 {
+            source.push_str(&format!("Worker().work(total, {});\n", n));
+        }
+        let statements = Parser::new(Scanner::new(&source).scan_tokens().unwrap())
+            .parse()
+            .unwrap();
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut interpreter = Interpreter::with_output(output.clone());
+        interpreter.interpret(&statements).unwrap();
+        assert_eq!(output.lock().unwrap().len(), 0);
+
+        // Every add has been processed once `interpret` returns, so a report
+        // sent after it sees the full sum.
+        let report = Parser::new(Scanner::new("total.report();").scan_tokens().unwrap())
+            .parse()
+            .unwrap();
+        interpreter.interpret(&report).unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.lock().unwrap()), "1275\n");
+    }
+
+    #[test]
+    fn fn_methods_run_synchronously_on_self() {
+        let source = "
+            actor Greeter {
+                let name = \"lok\";
+                fn greeting(punctuation) { self.name + punctuation; }
+                be greet() { print(self.greeting(\"!\")); }
+            }
+            Greeter().greet();
+        ";
+        assert_eq!(run(source).unwrap(), "lok!\n");
+    }
+
+    #[test]
+    fn calling_fn_of_another_actor_is_an_error() {
+        let source = "
+            actor Secret {
+                fn reveal() { 42; }
+            }
+            Secret().reveal();
+        ";
+        let err = run(source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Cannot call fn 'reveal' of another actor, only its behaviours"
+        );
+        assert!(err.note.is_some());
+    }
+
+    #[test]
+    fn fields_are_private_to_their_actor() {
+        let source = "
+            actor Account {
+                let mut balance = 10;
+            }
+            Account().balance;
+        ";
+        let err = run(source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Cannot access field 'balance' of another actor 'Account'"
+        );
+    }
+
+    #[test]
+    fn behaviour_errors_are_reported_after_the_run() {
+        let source = "
+            actor Broken {
+                let limit = 1;
+                be go() { self.limit = 2; }
+            }
+            Broken().go();
+            print(\"sent\");
+        ";
+        let err = run(source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Cannot assign twice to immutable field 'limit'"
+        );
+        assert_eq!(err.span.line, 4);
+    }
+
+    #[test]
+    fn every_behaviour_error_is_reported() {
+        let source = "
+            actor Broken {
+                be go(n) { n / 0; }
+            }
+            Broken().go(1);
+            Broken().go(2);
+            Broken().go(3);
+            1 / 0;
+        ";
+        let statements = Parser::new(Scanner::new(source).scan_tokens().unwrap())
+            .parse()
+            .unwrap();
+        let errors = Interpreter::new().interpret(&statements).unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|err| err.span.line).collect();
+        // The error that stopped the program first, then the behaviours'.
+        assert_eq!(lines, [8, 3, 3, 3]);
+        assert!(errors.iter().all(|err| err.message == "Division by zero"));
+    }
+
+    #[test]
+    fn closures_declared_in_an_actor_cannot_be_sent() {
+        let source = "
+            actor Counter {
+                let mut count = 0;
+                be share(to) {
+                    fn bump() { self.count = self.count + 1; }
+                    to.take(bump);
+                }
+                be keep() {
+                    fn bump() { self.count = self.count + 1; }
+                    self.take((1, bump));
+                }
+                be take(f) {}
+            }
+            actor Thief {
+                be take(f) { f(); }
+            }
+            Counter().share(Thief());
+        ";
+        let err = run(source).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Cannot send fn 'bump' to another actor, it was declared in actor 'Counter'"
+        );
+        assert_eq!(err.span.line, 6);
+
+        // Sending it back to the actor that declared it is fine.
+        let source = source.replace("Counter().share(Thief());", "Counter().keep();");
+        assert_eq!(run(&source).unwrap(), "");
+    }
+
+    #[test]
+    fn functions_and_return() {
+        let source = "
+            fn add(a: Int, b: Int) { a + b }
+            fn first(n) {
+                { return n; }
+                99;
+            }
+            fn twice(f, x) { f(f(x, x), x) }
+            first(7) + twice(add, 1)
+        ";
+        assert_eq!(eval(source).unwrap(), Value::Int(10));
+        assert_eq!(
+            eval("fn nothing() { return; 1; } nothing()").unwrap(),
+            Value::Nil
+        );
+    }
+
+    #[test]
+    fn closures_capture_their_environment() {
+        let source = "
+            fn make_counter() {
+                let mut count = 0;
+                fn increment() {
+                    count = count + 1;
+                    return count;
+                }
+                return increment;
+            }
+            let counter = make_counter();
+            counter();
+            counter();
+            let other = make_counter();
+            other();
+            counter()
+        ";
+        assert_eq!(eval(source).unwrap(), Value::Int(3));
+    }
+
+    #[test]
+    fn wrong_number_of_arguments_is_an_error() {
+        let err = eval("fn add(a, b) { a + b } add(1)").unwrap_err();
+        assert_eq!(err.message, "Expected 2 arguments but got 1");
+
+        let err = eval("let x = 1; x()").unwrap_err();
+        assert_eq!(err.message, "Can only call functions and actors, got int");
+    }
+
+    #[test]
+    fn overloads_dispatch_on_argument_types() {
+        let source = "
+            fn describe(x: Int) { \"int\" }
+            fn describe(x: String) { \"string\" }
+            fn describe(x) { \"anything\" }
+            describe(1) + describe(\"a\") + describe(true)
+        ";
+        assert_eq!(
+            eval(source).unwrap(),
+            Value::String("intstringanything".to_string())
+        );
+
+        let err = eval("fn f(x: Int) {} fn f(x: String) {} f(nil)").unwrap_err();
+        assert_eq!(err.message, "No overload of 'f' accepts (Nil)");
+    }
+
+    #[test]
+    fn overloaded_methods_dispatch_at_runtime() {
+        let source = "
+            actor Main {
+                let mut age = 23;
+                be update_age(by_how_much) { self.updating_age(by_how_much); }
+                be report() { print(self.age); }
+                fn updating_age(by: Int) { self.age = self.age + by; }
+                fn updating_age(by: String) { self.age = self.age + 10; }
+            }
+            let main = Main();
+            main.update_age(1);
+            main.update_age(\"ten\");
+            main.report();
+        ";
+        assert_eq!(run(source).unwrap(), "34\n");
+    }
+
+    #[test]
+    fn pipes_thread_the_value_as_first_argument() {
+        let source = "
+            fn add(a, b) { a + b }
+            fn double(a) { a * 2 }
+            let newly = \" 41 \"
+                |> String.trim_string()
+                |> String.parse_to_int();
+            newly |> add(1) |> double
+        ";
+        assert_eq!(eval(source).unwrap(), Value::Int(84));
+
+        let err = eval("\"forty\" |> String.parse_to_int()").unwrap_err();
+        assert_eq!(err.message, "Cannot parse 'forty' as an Int");
+        let err = eval("String.reverse(\"a\")").unwrap_err();
+        assert_eq!(err.message, "Module 'String' has no function 'reverse'");
+    }
+
+    #[test]
+    fn pipes_into_behaviours() {
+        let source = "
+            actor Log {
+                be log(line, suffix) { print(line + suffix); }
+            }
+            let log = Log();
+            \"hi\" |> String.to_upper() |> log.log(\"!\");
+        ";
+        assert_eq!(run(source).unwrap(), "HI!\n");
+    }
+
+    #[test]
+    fn interpolated_strings() {
+        let source = r#"
+            actor Person {
+                let mut age = 23;
+                be report(unit) { print("Age: ${self.age} ${unit}, next ${self.age + 1}\t(${(1, 2.0)})"); }
+            }
+            Person().report("years");
+        "#;
+        assert_eq!(run(source).unwrap(), "Age: 23 years, next 24\t((1, 2.0))\n");
+    }
+
+    #[test]
+    fn match_literals_bindings_and_guards() {
+        let source = "
+            fn describe(n) {
+                match n {
+                    0 => \"zero\",
+                    -1 => \"minus one\",
+                    \"lok\" => \"a name\",
+                    nil => \"nothing\",
+                    x: Int if x > 100 => \"big\",
+                    x: Int => \"int \" + String.to_upper(\"x\"),
+                    _ => \"other\"
+                }
+            }
+            print(describe(0));
+            print(describe(-1));
+            print(describe(101));
+            print(describe(\"lok\"));
+            print(describe(nil));
+            print(describe(7));
+            print(describe(true));
+        ";
+        assert_eq!(
+            run(source).unwrap(),
+            "zero\nminus one\nbig\na name\nnothing\nint X\nother\n"
+        );
+    }
+
+    #[test]
+    fn match_destructures_tuples_and_records() {
+        let source = "
+            let point = (1, 2);
+            match point {
+                (0, y) => print(y),
+                (x, y) if x < y => print(x + y),
+                _ => print(\"no\")
+            }
+            let person = {name: \"lok\", age: 3};
+            match person {
+                {name, age: 3} => print(name),
+                {name} => print(\"someone\")
+            }
+            print(person.name);
+            print(point);
+            person
+        ";
+        assert_eq!(run(source).unwrap(), "3\nlok\nlok\n(1, 2)\n");
+        assert_eq!(
+            eval("let p = {name: \"lok\", age: 3}; p")
+                .unwrap()
+                .to_string(),
+            "{name: lok, age: 3}"
+        );
+        let err = eval("let p = {name: 1}; p.age").unwrap_err();
+        assert_eq!(err.message, "Record has no field 'age'");
+    }
+
+    #[test]
+    fn match_arms_bind_in_their_own_scope_and_can_return() {
+        let source = "
+            fn first_positive(a, b) {
+                match (a, b) {
+                    (x, _) if x > 0 => { return x; }
+                    (_, y) if y > 0 => y,
+                    _ => nil
+                }
+                \"unreachable\"
+            }
+            let x = \"outer\";
+            match 1 { x => x }
+            print(x);
+            print(first_positive(2, 3));
+            print(first_positive(-2, 3));
+        ";
+        assert_eq!(run(source).unwrap(), "outer\n2\nunreachable\n");
+    }
+
+    #[test]
+    fn unmatched_value_is_an_error() {
+        let err = eval("match 3 { 1 => 1, 2 => 2 }").unwrap_err();
+        assert_eq!(err.message, "No match arm accepts Int");
+        assert_eq!(err.note.as_deref(), Some("the value was 3"));
+        assert_eq!(err.span.start, 0);
+    }
+}
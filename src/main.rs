@@ -1,76 +1,358 @@
+mod ast_printer;
+mod capability;
+mod checker;
+mod cst;
+mod diagnostics;
+mod environment;
 mod expr;
+mod formatter;
+mod interpreter;
+mod overload;
 mod parser;
+mod pattern;
+mod repl;
+mod runtime;
 mod scanner;
+mod stdlib;
+mod stmt;
+mod test_runner;
+mod type_expr;
 
-use crate::scanner::*;
+use crate::ast_printer::{print_ast, AstFormat};
+use crate::checker::*;
+use crate::cst::SyntaxTree;
+use crate::diagnostics::*;
+use crate::interpreter::*;
+use crate::scanner::Token;
+use crate::stmt::Stmt;
 
 use std::env;
 use std::fs;
-use std::io::{self, Write};
 use std::process::exit;
 
-fn run_file(path: &str) -> Result<(), String> {
-    match fs::read_to_string(path) {
-        Ok(content) => return run(&content),
-        Err(err) => return Err(err.to_string()),
+// Exit codes, from sysexits.h.
+const EX_USAGE: i32 = 64;
+const EX_DATAERR: i32 = 65;
+const EX_NOINPUT: i32 = 66;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+
+const USAGE: &str = "Usage: loklang [--error-format=human|json] [--emit=tokens|ast|ir]
+               [--ast-format=sexpr|tree] <command> [args]
+
+Commands:
+    run <file>              check and run a program
+    check <file>...         report errors and warnings without running
+    fmt [--check] <file>... format files, or list the unformatted ones
+    tokens <file>           print the tokens of a file
+    ast <file>              print the syntax tree of a file
+    repl                    start the interactive prompt, the default,
+                            `:help` lists its commands
+    test <path>...          run programs and compare their output with
+                            their `// expect: ` comments
+    build <file>            check a program and print what --emit asks for";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Emit {
+    Tokens,
+    Ast,
+    Ir,
+}
+
+#[derive(Clone)]
+struct Options {
+    format: ErrorFormat,
+    emit: Vec<Emit>,
+    ast_format: AstFormat,
+}
+
+// Why a command failed: what is left to print on stderr and the exit code.
+struct Failure {
+    code: i32,
+    message: String,
+}
+
+impl Failure {
+    fn new(code: i32, message: String) -> Self {
+        Self { code, message }
     }
 }
 
-fn run(content: &str) -> Result<(), String> {
-    let mut scanner = Scanner::new(content);
-    let tokens = scanner.scan_tokens()?;
+fn read(path: &str) -> Result<String, Failure> {
+    fs::read_to_string(path)
+        .map_err(|err| Failure::new(EX_NOINPUT, format!("Could not read {}: {}\n", path, err)))
+}
 
+fn render(diagnostics: &[Diagnostic], content: &str, file: &str, format: ErrorFormat) -> String {
+    let rendered: Vec<String> = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(content, file, format))
+        .collect();
+    match format {
+        ErrorFormat::Human => rendered.join("\n"),
+        ErrorFormat::Json => rendered.join("\n") + "\n",
+    }
+}
+
+// One token a line: where it starts, its type and its lexeme.
+fn token_listing(tokens: &[Token]) -> String {
+    let mut listing = String::new();
     for token in tokens {
-        println!("{:?}", token);
+        listing.push_str(&format!(
+            "{:<8}{:<20}{}\n",
+            token.span.to_string(),
+            token.token_type.to_string(),
+            token.lexeme.replace('\n', "\\n")
+        ));
+    }
+    listing
+}
+
+// Scans, parses and checks `content`, printing what `--emit` asks for on the
+// way. Warnings go to stderr, errors come back rendered in `format`.
+fn front_end(
+    content: &str,
+    file: &str,
+    options: &Options,
+    checker: &mut Checker,
+) -> Result<Vec<Stmt>, Failure> {
+    front_end_of(SyntaxTree::scan(content), content, file, options, checker)
+}
+
+// `front_end` for a tree scanned from `content`, which the REPL changes
+// before it is parsed.
+fn front_end_of(
+    tree: SyntaxTree,
+    content: &str,
+    file: &str,
+    options: &Options,
+    checker: &mut Checker,
+) -> Result<Vec<Stmt>, Failure> {
+    let render = |diagnostics: Vec<Diagnostic>| {
+        Failure::new(
+            EX_DATAERR,
+            render(&diagnostics, content, file, options.format),
+        )
+    };
+
+    // Tokens are listed even when some of the source couldn't be scanned.
+    if options.emit.contains(&Emit::Tokens) {
+        print!("{}", token_listing(tree.tokens()));
+    }
+    if !tree.errors().is_empty() {
+        return Err(render(tree.errors().iter().map(Diagnostic::from).collect()));
+    }
+
+    let statements = match tree.ast() {
+        Ok(statements) => statements,
+        Err(errors) => return Err(render(errors.iter().map(Diagnostic::from).collect())),
+    };
+    if options.emit.contains(&Emit::Ast) {
+        print!("{}", print_ast(&statements, options.ast_format));
+    }
+    if options.emit.contains(&Emit::Ir) {
+        return Err(Failure::new(
+            EX_USAGE,
+            "--emit=ir is not available, programs are interpreted from the syntax tree\n"
+                .to_string(),
+        ));
+    }
+
+    let checked = checker.check(&statements);
+    let warnings: Vec<Diagnostic> = checker
+        .take_warnings()
+        .iter()
+        .map(Diagnostic::type_warning)
+        .collect();
+    if !warnings.is_empty() {
+        eprint!("{}", render(warnings).message);
+    }
+    if let Err(errors) = checked {
+        return Err(render(errors.iter().map(Diagnostic::from).collect()));
+    }
+    Ok(statements)
+}
+
+fn run(content: &str, file: &str, options: &Options) -> Result<(), Failure> {
+    let statements = front_end(content, file, options, &mut Checker::new())?;
+    let mut interpreter = Interpreter::new();
+    match interpreter.interpret(&statements) {
+        Ok(Value::Nil) => Ok(()),
+        Ok(value) => {
+            println!("{}", value);
+            Ok(())
+        }
+        Err(errors) => Err(Failure::new(
+            EX_SOFTWARE,
+            render(
+                &errors.iter().map(Diagnostic::from).collect::<Vec<_>>(),
+                content,
+                file,
+                options.format,
+            ),
+        )),
+    }
+}
+
+fn single_path(paths: &[String], command: &str) -> Result<String, Failure> {
+    match paths {
+        [path] => Ok(path.clone()),
+        _ => Err(Failure::new(
+            EX_USAGE,
+            format!("Usage: loklang {} <file>\n", command),
+        )),
     }
-    return Ok(());
 }
-fn run_promt() -> Result<(), String> {
-    loop {
-        print!("> ");
-        let mut buffer = String::new();
-        match io::stdout().flush() {
-            Ok(_) => (),
-            Err(_) => return Err("Could not flush stdout".to_string()),
+
+// Every file is checked, the first failure decides the exit code.
+fn check_files(paths: &[String], options: &Options) -> Result<(), Failure> {
+    if paths.is_empty() {
+        return Err(Failure::new(
+            EX_USAGE,
+            "Usage: loklang check <file>...\n".to_string(),
+        ));
+    }
+    let mut code = None;
+    for path in paths {
+        let result =
+            read(path).and_then(|content| front_end(&content, path, options, &mut Checker::new()));
+        if let Err(err) = result {
+            eprint!("{}", err.message);
+            code.get_or_insert(err.code);
         }
-        let stdin = io::stdin();
-        match stdin.read_line(&mut buffer) {
-            Ok(n) => {
-                if n <= 1 {
-                    break;
+    }
+    match code {
+        Some(code) => Err(Failure::new(code, String::new())),
+        None => Ok(()),
+    }
+}
+
+// `fmt [--check] <file>...` rewrites the files in the canonical style. With
+// `--check` nothing is written, the files that would change are listed and
+// the exit code is 1 if there are any.
+fn fmt_files(args: &[String], options: &Options) -> Result<(), Failure> {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&String> = args.iter().filter(|arg| *arg != "--check").collect();
+    if paths.is_empty() {
+        return Err(Failure::new(
+            EX_USAGE,
+            "Usage: loklang fmt [--check] <file>...\n".to_string(),
+        ));
+    }
+
+    let mut code = None;
+    for path in paths {
+        let content = match read(path) {
+            Ok(content) => content,
+            Err(err) => {
+                eprint!("{}", err.message);
+                code.get_or_insert(err.code);
+                continue;
+            }
+        };
+        match formatter::format_source(&content) {
+            Ok(formatted) if formatted == content => (),
+            Ok(_) if check => {
+                println!("Would reformat {}", path);
+                code.get_or_insert(1);
+            }
+            Ok(formatted) => {
+                if let Err(err) = fs::write(path, formatted) {
+                    eprintln!("Could not write {}: {}", path, err);
+                    code.get_or_insert(EX_IOERR);
                 }
             }
-            Err(_) => return Err("Could not read the line".to_string()),
-        }
-        println!("ECHO: {}", buffer);
-        match run(&buffer) {
-            Ok(_) => (),
-            Err(_msg) => return Err("Problem with the buffer".to_string()),
+            Err(diagnostics) => {
+                eprint!("{}", render(&diagnostics, &content, path, options.format));
+                code.get_or_insert(EX_DATAERR);
+            }
         }
     }
-    Ok(())
+    match code {
+        Some(code) => Err(Failure::new(code, String::new())),
+        None => Ok(()),
+    }
 }
-fn main() {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() > 2 {
-        println!("Usage: jlox [script]");
-        exit(64);
-    } else if args.len() == 2 {
-        match run_file(&args[1]) {
-            Ok(_) => exit(0),
-            Err(msg) => {
-                println!("Error:\n{}", msg);
-                exit(1);
+
+fn command(name: &str, args: &[String], options: &Options) -> Result<(), Failure> {
+    match name {
+        "run" => {
+            let path = single_path(args, name)?;
+            run(&read(&path)?, &path, options)
+        }
+        "check" => check_files(args, options),
+        "fmt" => fmt_files(args, options),
+        // `tokens` and `ast` are `build` emitting only that.
+        "tokens" | "ast" | "build" => {
+            let path = single_path(args, name)?;
+            let mut options = options.clone();
+            match name {
+                "tokens" => options.emit = vec![Emit::Tokens],
+                "ast" => options.emit = vec![Emit::Ast],
+                _ => (),
             }
+            front_end(&read(&path)?, &path, &options, &mut Checker::new()).map(|_| ())
         }
-    } else {
-        match run_promt() {
-            Ok(_) => exit(0),
-            Err(msg) => {
-                println!("ERROR\n{}", msg);
-                exit(1);
+        "repl" if args.is_empty() => repl::run_repl(options),
+        "test" => test_runner::run_tests(args),
+        _ => Err(Failure::new(EX_USAGE, format!("{}\n", USAGE))),
+    }
+}
+
+fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    // Global flags may come anywhere, the rest is the command and its args.
+    let mut options = Options {
+        format: ErrorFormat::Human,
+        emit: vec![],
+        ast_format: AstFormat::SExpr,
+    };
+    let mut i = 0;
+    while i < args.len() {
+        if let Some(format) = args[i].strip_prefix("--error-format=") {
+            options.format = match format {
+                "human" => ErrorFormat::Human,
+                "json" => ErrorFormat::Json,
+                other => {
+                    eprintln!("Unknown error format: {}", other);
+                    exit(EX_USAGE);
+                }
+            };
+        } else if let Some(format) = args[i].strip_prefix("--ast-format=") {
+            options.ast_format = match format {
+                "sexpr" => AstFormat::SExpr,
+                "tree" => AstFormat::Tree,
+                other => {
+                    eprintln!("Unknown AST format: {}", other);
+                    exit(EX_USAGE);
+                }
+            };
+        } else if let Some(emit) = args[i].strip_prefix("--emit=") {
+            for kind in emit.split(',') {
+                options.emit.push(match kind {
+                    "tokens" => Emit::Tokens,
+                    "ast" => Emit::Ast,
+                    "ir" => Emit::Ir,
+                    other => {
+                        eprintln!("Unknown --emit kind: {}", other);
+                        exit(EX_USAGE);
+                    }
+                });
             }
+        } else {
+            i += 1;
+            continue;
         }
+        args.remove(i);
+    }
+
+    let result = match args.split_first() {
+        Some((name, rest)) => command(name, rest, &options),
+        None => repl::run_repl(&options),
+    };
+    if let Err(failure) = result {
+        eprint!("{}", failure.message);
+        exit(failure.code);
     }
 }
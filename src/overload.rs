@@ -0,0 +1,279 @@
+use crate::interpreter::Value;
+use crate::stmt::{Function, Param};
+use crate::type_expr::TypeExpr;
+
+// Functions and methods may be overloaded on the types of their parameters:
+//
+//   fn updating_age(by: Int) { ... }
+//   fn updating_age(by: String) { ... }
+//
+// The overload is picked when the call runs, from the values of the arguments,
+// so an argument of a union type dispatches to whichever overload accepts the
+// value it holds. Parameters without a type accept any value.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverloadError {
+    pub message: String,
+    pub note: Option<String>,
+}
+
+// Two declarations with the same parameter types can't be told apart, the
+// later one replaces the earlier.
+pub fn same_signature(a: &Function, b: &Function) -> bool {
+    a.params.len() == b.params.len()
+        && a.params
+            .iter()
+            .zip(&b.params)
+            .all(|(a, b)| a.type_annotation == b.type_annotation)
+}
+
+// Picks the overload accepting `arguments`. When several do, the one with the
+// most typed parameters wins, e.g. `f(x: Int)` over `f(x)`.
+pub fn resolve<'a>(
+    candidates: &[&'a Function],
+    arguments: &[Value],
+) -> Result<&'a Function, OverloadError> {
+    let name = &candidates[0].name.lexeme;
+    if let [function] = candidates {
+        if function.params.len() != arguments.len() {
+            return Err(OverloadError {
+                message: format!(
+                    "Expected {} arguments but got {}",
+                    function.params.len(),
+                    arguments.len()
+                ),
+                note: None,
+            });
+        }
+    }
+
+    let applicable: Vec<&Function> = candidates
+        .iter()
+        .copied()
+        .filter(|function| applies(function, arguments))
+        .collect();
+    let most_specific = applicable.iter().map(|f| specificity(f)).max();
+    let best: Vec<&Function> = applicable
+        .into_iter()
+        .filter(|f| Some(specificity(f)) == most_specific)
+        .collect();
+
+    match best.as_slice() {
+        [function] => Ok(function),
+        [] => Err(OverloadError {
+            message: format!(
+                "No overload of '{}' accepts ({})",
+                name,
+                argument_types(arguments)
+            ),
+            note: Some(candidates_note(candidates)),
+        }),
+        ambiguous => Err(OverloadError {
+            message: format!(
+                "Ambiguous call to '{}', {} overloads accept ({})",
+                name,
+                ambiguous.len(),
+                argument_types(arguments)
+            ),
+            note: Some(candidates_note(ambiguous)),
+        }),
+    }
+}
+
+fn applies(function: &Function, arguments: &[Value]) -> bool {
+    function.params.len() == arguments.len()
+        && function
+            .params
+            .iter()
+            .zip(arguments)
+            .all(|(param, argument)| accepts(param, argument))
+}
+
+fn specificity(function: &Function) -> usize {
+    function
+        .params
+        .iter()
+        .filter(|param| param.type_annotation.is_some())
+        .count()
+}
+
+pub fn accepts(param: &Param, value: &Value) -> bool {
+    match &param.type_annotation {
+        Some(annotation) => has_type(value, annotation),
+        None => true,
+    }
+}
+
+// A union accepts the values of any of its members, a tuple type the tuples of
+// its length whose elements have the element types.
+pub fn has_type(value: &Value, type_expr: &TypeExpr) -> bool {
+    let expected = match type_expr {
+        TypeExpr::Named { name, .. } => name.lexeme.as_str(),
+        TypeExpr::Union { types, .. } => return types.iter().any(|t| has_type(value, t)),
+        TypeExpr::Tuple { types, .. } => {
+            return match value {
+                Value::Tuple(values) => {
+                    values.len() == types.len()
+                        && values.iter().zip(types).all(|(v, t)| has_type(v, t))
+                }
+                _ => false,
+            }
+        }
+    };
+    match (expected, value) {
+        ("Number", Value::Int(_) | Value::Float(_)) => true,
+        ("Int", Value::Int(_)) => true,
+        ("Float", Value::Float(_)) => true,
+        ("String", Value::String(_)) => true,
+        ("Bool", Value::Bool(_)) => true,
+        ("Nil", Value::Nil) => true,
+        ("Fn", Value::Function(_) | Value::NativeFunction(_)) => true,
+        (expected, Value::Actor(actor)) => actor.class.name.lexeme == expected,
+        _ => false,
+    }
+}
+
+// The name a parameter type would use for `value`.
+pub fn type_of(value: &Value) -> String {
+    match value {
+        Value::Int(_) => "Int".to_string(),
+        Value::Float(_) => "Float".to_string(),
+        Value::String(_) => "String".to_string(),
+        Value::Bool(_) => "Bool".to_string(),
+        Value::Nil => "Nil".to_string(),
+        Value::Actor(actor) => actor.class.name.lexeme.clone(),
+        Value::ActorClass(_) => "ActorClass".to_string(),
+        Value::Function(_) | Value::NativeFunction(_) => "Fn".to_string(),
+        Value::Module(_) => "Module".to_string(),
+        Value::Tuple(values) if values.len() == 1 => format!("({},)", type_of(&values[0])),
+        Value::Tuple(values) => format!("({})", argument_types(values)),
+        Value::Record(fields) => {
+            let fields: Vec<String> = fields
+                .iter()
+                .map(|(name, value)| format!("{}: {}", name, type_of(value)))
+                .collect();
+            format!("{{{}}}", fields.join(", "))
+        }
+    }
+}
+
+fn argument_types(arguments: &[Value]) -> String {
+    let types: Vec<String> = arguments.iter().map(type_of).collect();
+    types.join(", ")
+}
+
+fn candidates_note(candidates: &[&Function]) -> String {
+    let signatures: Vec<String> = candidates
+        .iter()
+        .map(|function| {
+            let params: Vec<String> = function.params.iter().map(|p| p.to_string()).collect();
+            format!("{}({})", function.name.lexeme, params.join(", "))
+        })
+        .collect();
+    format!("candidates are {}", signatures.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+    use crate::stmt::Stmt;
+
+    fn functions(source: &str) -> Vec<Function> {
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        Parser::new(tokens)
+            .parse()
+            .unwrap()
+            .into_iter()
+            .map(|stmt| match stmt {
+                Stmt::Function(function) => function,
+                other => panic!("expected a function, got {}", other),
+            })
+            .collect()
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    #[test]
+    fn resolves_by_argument_type() {
+        let overloads = functions("fn f(by: Int) {} fn f(by: String) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let chosen = resolve(&candidates, &[Value::Int(1)]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "by: Int");
+        let chosen = resolve(&candidates, &[string("1")]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "by: String");
+    }
+
+    #[test]
+    fn union_parameters_accept_any_member() {
+        let overloads = functions("fn f(by: (Int | String)) {} fn f(by: Bool) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let chosen = resolve(&candidates, &[string("1")]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "by: (Int | String)");
+        let chosen = resolve(&candidates, &[Value::Int(1)]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "by: (Int | String)");
+        let chosen = resolve(&candidates, &[Value::Bool(false)]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "by: Bool");
+    }
+
+    #[test]
+    fn typed_parameters_win_over_untyped() {
+        let overloads = functions("fn f(x) {} fn f(x: Int) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let chosen = resolve(&candidates, &[Value::Int(1)]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "x: Int");
+        let chosen = resolve(&candidates, &[Value::Bool(true)]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "x");
+    }
+
+    #[test]
+    fn no_applicable_overload_is_an_error() {
+        let overloads = functions("fn f(by: Int) {} fn f(by: String) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let err = resolve(&candidates, &[Value::Bool(true)]).unwrap_err();
+        assert_eq!(err.message, "No overload of 'f' accepts (Bool)");
+        assert_eq!(
+            err.note.unwrap(),
+            "candidates are f(by: Int), f(by: String)"
+        );
+    }
+
+    #[test]
+    fn ambiguous_overloads_are_an_error() {
+        let overloads = functions("fn f(a: Int, b) {} fn f(a, b: Int) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let err = resolve(&candidates, &[Value::Int(1), Value::Int(2)]).unwrap_err();
+        assert_eq!(
+            err.message,
+            "Ambiguous call to 'f', 2 overloads accept (Int, Int)"
+        );
+    }
+
+    #[test]
+    fn tuple_parameters_accept_tuples_of_their_types() {
+        let overloads = functions("fn f(p: (Int, String)) {} fn f(p: (Int, Int)) {}");
+        let candidates: Vec<&Function> = overloads.iter().collect();
+
+        let pair = Value::Tuple(vec![Value::Int(1), string("a")]);
+        let chosen = resolve(&candidates, &[pair]).unwrap();
+        assert_eq!(chosen.params[0].to_string(), "p: (Int, String)");
+
+        let err = resolve(&candidates, &[Value::Tuple(vec![Value::Nil])]).unwrap_err();
+        assert_eq!(err.message, "No overload of 'f' accepts ((Nil,))");
+    }
+
+    #[test]
+    fn signatures_compare_parameter_types() {
+        let overloads = functions("fn f(a: Int) {} fn f(b: Int) {} fn f(a) {}");
+        assert!(same_signature(&overloads[0], &overloads[1]));
+        assert!(!same_signature(&overloads[0], &overloads[2]));
+    }
+}
@@ -1,138 +1,900 @@
-use crate::expr::{Expr, Expr::*, LiteralValue};
-use crate::scanner::{Token, TokenType, TokenType::*};
+use crate::capability::Capability;
+use crate::expr::{Expr, Expr::*, LiteralValue, MatchArm};
+use crate::overload;
+use crate::pattern::Pattern;
+use crate::scanner::{self, Span, Token, TokenType, TokenType::*};
+use crate::stmt::{Function, FunctionKind, Param, Stmt};
+use crate::type_expr::TypeExpr;
+
+use std::fmt;
+// Keeps `String` the type usable next to the `TokenType::String` variant.
+use std::string::String;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub lexeme: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    fn new(token: &Token, message: &str) -> Self {
+        Self {
+            message: message.to_string(),
+            lexeme: token.lexeme.clone(),
+            span: token.span,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let location = if self.lexeme.is_empty() {
+            "end".to_string()
+        } else {
+            format!("'{}'", self.lexeme)
+        };
+        write!(
+            f,
+            "[line {}] Error at {}: {}",
+            self.span, location, self.message
+        )
+    }
+}
 
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    errors: Vec<ParseError>,
+    // How many function bodies the parser is inside of, `return` is only
+    // valid when non-zero.
+    function_depth: usize,
 }
 
-macro_rules! match_tokens {
-    ($parser:ident, $($token:ident),+) => {
-        {
-            let mut result = false;
-            {
-            $( result |= $parser.match_token($token); )*
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            current: 0,
+            errors: vec![],
+            function_depth: 0,
+        }
+    }
+
+    // Keeps parsing after a syntax error so that every error in the file is
+    // reported in one run.
+    pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut statements = vec![];
+        while !self.is_at_end() {
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize();
+                }
             }
+        }
 
-            result
+        if self.errors.is_empty() {
+            Ok(statements)
+        } else {
+            Err(std::mem::take(&mut self.errors))
         }
+    }
 
-    };
-}
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+    fn declaration(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_token(&ACTOR) {
+            self.actor_declaration()
+        } else if self.match_token(&FUN) {
+            Ok(Stmt::Function(self.function(FunctionKind::Function)?))
+        } else if self.match_token(&LET) {
+            self.let_declaration()
+        } else {
+            self.statement()
+        }
+    }
+    // Declarations take the `///` comments before their keyword as their doc.
+    fn actor_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.previous().span;
+        let doc = self.previous().doc_comment();
+        let name = self.consume(Identifier, "Expected actor name after 'actor'")?;
+        self.consume(LeftBrace, "Expected '{' before actor body")?;
+
+        let mut fields = vec![];
+        let mut methods = vec![];
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            if let Err(err) = self.actor_member(&mut fields, &mut methods) {
+                self.errors.push(err);
+                self.synchronize_member();
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after actor body")?;
+
+        Ok(Stmt::Actor {
+            name,
+            fields,
+            methods,
+            doc,
+            span: start.to(self.previous().span),
+        })
+    }
+    fn actor_member(
+        &mut self,
+        fields: &mut Vec<Stmt>,
+        methods: &mut Vec<Function>,
+    ) -> Result<(), ParseError> {
+        if self.match_token(&LET) {
+            fields.push(self.let_declaration()?);
+        } else if self.match_token(&FUN) {
+            let method = self.function(FunctionKind::Function)?;
+            self.check_overload(methods, &method);
+            methods.push(method);
+        } else if self.match_token(&BE) {
+            let method = self.function(FunctionKind::Behaviour)?;
+            self.check_overload(methods, &method);
+            methods.push(method);
+        } else {
+            return Err(ParseError::new(
+                self.peek(),
+                "Expected 'let', 'fn' or 'be' in actor body",
+            ));
+        }
+        Ok(())
+    }
+    // Methods may be overloaded on their parameter types, but the overloads
+    // must all be `fn`s or all `be`s and differ in their types.
+    fn check_overload(&mut self, methods: &[Function], method: &Function) {
+        let name = &method.name;
+        for other in methods.iter().filter(|m| m.name.lexeme == name.lexeme) {
+            let message = if other.kind != method.kind {
+                format!("'{}' is declared both as fn and be", name.lexeme)
+            } else if overload::same_signature(other, method) {
+                format!(
+                    "'{}' is already declared with the same parameter types",
+                    name.lexeme
+                )
+            } else {
+                continue;
+            };
+            self.errors.push(ParseError::new(name, &message));
+            return;
+        }
+    }
+    // Called with the `fn` or `be` keyword already consumed.
+    fn function(&mut self, kind: FunctionKind) -> Result<Function, ParseError> {
+        let start = self.previous().span;
+        let doc = self.previous().doc_comment();
+        let name = self.consume(Identifier, &format!("Expected {} name", kind))?;
+        self.consume(LeftParen, &format!("Expected '(' after {} name", kind))?;
+
+        let mut params = vec![];
+        if !self.check(&RightParen) {
+            loop {
+                let name = self.consume(Identifier, "Expected parameter name")?;
+                let type_annotation = if self.match_token(&Colon) {
+                    Some(self.type_annotation("Expected parameter type after ':'")?)
+                } else {
+                    None
+                };
+                params.push(Param {
+                    name,
+                    type_annotation,
+                });
+                if !self.match_token(&Comma) {
+                    break;
+                }
+            }
+        }
+        self.consume(RightParen, "Expected ')' after parameters")?;
+        let return_type = if self.match_token(&Arrow) {
+            Some(self.type_annotation("Expected return type after '->'")?)
+        } else {
+            None
+        };
+
+        self.consume(LeftBrace, &format!("Expected '{{' before {} body", kind))?;
+        self.function_depth += 1;
+        let body = self.block();
+        self.function_depth -= 1;
+        let body = body?;
+
+        Ok(Function {
+            kind,
+            name,
+            params,
+            return_type,
+            body,
+            doc,
+            span: start.to(self.previous().span),
+        })
+    }
+    fn let_declaration(&mut self) -> Result<Stmt, ParseError> {
+        let start = self.previous().span;
+        let doc = self.previous().doc_comment();
+        let mutable = self.match_token(&MUT);
+        let name = self.consume(Identifier, "Expected variable name after 'let'")?;
+        let type_annotation = if self.match_token(&Colon) {
+            Some(self.type_annotation("Expected variable type after ':'")?)
+        } else {
+            None
+        };
+
+        let initializer = if self.match_token(&Equal) {
+            Some(self.expression()?)
+        } else {
+            None
+        };
+        self.consume(Semicolon, "Expected ';' after variable declaration")?;
+
+        Ok(Stmt::Let {
+            name,
+            mutable,
+            type_annotation,
+            initializer,
+            doc,
+            span: start.to(self.previous().span),
+        })
+    }
+    fn type_annotation(&mut self, msg: &str) -> Result<TypeExpr, ParseError> {
+        if !self.check(&Identifier) && !self.check(&LeftParen) {
+            return Err(ParseError::new(self.peek(), msg));
+        }
+        self.type_expr()
+    }
+    // Nested unions are flattened, `(Int | String) | Bool` is a union of three.
+    fn type_expr(&mut self) -> Result<TypeExpr, ParseError> {
+        let first = self.type_primary()?;
+        if !self.check(&Bar) {
+            return Ok(first);
+        }
+
+        let start = first.span();
+        let mut types = vec![];
+        let mut member = first;
+        loop {
+            match member {
+                TypeExpr::Union { types: members, .. } => types.extend(members),
+                other => types.push(other),
+            }
+            if !self.match_token(&Bar) {
+                break;
+            }
+            member = self.type_primary()?;
+        }
+        Ok(TypeExpr::Union {
+            types,
+            span: start.to(self.previous().span),
+        })
+    }
+    // Capabilities aren't keywords, `box` or `tag` are fine variable names.
+    fn type_primary(&mut self) -> Result<TypeExpr, ParseError> {
+        if self.match_token(&Identifier) {
+            let name = self.previous().clone();
+            let capability = match Capability::from_name(&self.peek().lexeme) {
+                Some(capability) if self.check(&Identifier) => {
+                    self.advance();
+                    Some(capability)
+                }
+                _ => None,
+            };
+            return Ok(TypeExpr::Named {
+                span: name.span.to(self.previous().span),
+                name,
+                capability,
+            });
+        }
+        if !self.match_token(&LeftParen) {
+            return Err(ParseError::new(self.peek(), "Expected type"));
+        }
+
+        let start = self.previous().span;
+        let mut types = vec![];
+        let mut tuple = true;
+        if !self.check(&RightParen) {
+            types.push(self.type_expr()?);
+            tuple = self.check(&Comma);
+            while self.match_token(&Comma) && !self.check(&RightParen) {
+                types.push(self.type_expr()?);
+            }
+        }
+        self.consume(RightParen, "Expected ')' after type")?;
+        let span = start.to(self.previous().span);
+
+        // Parentheses around a single type only group it.
+        match types.pop() {
+            Some(TypeExpr::Union { types, .. }) if !tuple => Ok(TypeExpr::Union { types, span }),
+            Some(inner) if !tuple => Ok(inner),
+            last => {
+                types.extend(last);
+                Ok(TypeExpr::Tuple { types, span })
+            }
+        }
+    }
+    fn statement(&mut self) -> Result<Stmt, ParseError> {
+        if self.match_token(&RETURN) {
+            self.return_statement()
+        } else if self.match_token(&LeftBrace) {
+            let start = self.previous().span;
+            let statements = self.block()?;
+            Ok(Stmt::Block {
+                statements,
+                span: start.to(self.previous().span),
+            })
+        } else {
+            self.expression_statement()
+        }
+    }
+    fn return_statement(&mut self) -> Result<Stmt, ParseError> {
+        let keyword = self.previous().clone();
+        if self.function_depth == 0 {
+            return Err(ParseError::new(
+                &keyword,
+                "Cannot return from top-level code",
+            ));
+        }
+
+        let value = if self.check(&Semicolon) || self.check(&RightBrace) {
+            None
+        } else {
+            Some(self.expression()?)
+        };
+        if !self.check(&RightBrace) {
+            self.consume(Semicolon, "Expected ';' after return value")?;
+        }
+        Ok(Stmt::Return {
+            span: keyword.span.to(self.previous().span),
+            keyword,
+            value,
+        })
+    }
+    // Errors inside the block are recorded and the rest of the block is still
+    // parsed, so that a typo on one line doesn't hide the errors after it.
+    fn block(&mut self) -> Result<Vec<Stmt>, ParseError> {
+        let mut statements = vec![];
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            match self.declaration() {
+                Ok(stmt) => statements.push(stmt),
+                Err(err) => {
+                    self.errors.push(err);
+                    self.synchronize();
+                }
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after block")?;
+        Ok(statements)
+    }
+    // The trailing expression of a block or program may omit its ';'.
+    // A `match` ends with a '}' and, like a block, needs no ';' after it.
+    fn expression_statement(&mut self) -> Result<Stmt, ParseError> {
+        let expression = self.expression()?;
+        let ends_with_brace = matches!(expression, Match { .. });
+        if !ends_with_brace && !self.check(&RightBrace) && !self.is_at_end() {
+            self.consume(Semicolon, "Expected ';' after expression")?;
+        }
+        let span = expression.span().to(self.previous().span);
+        Ok(Stmt::Expression { expression, span })
     }
 
-    fn expression(&mut self) -> Expr {
-        self.equality()
+    fn expression(&mut self) -> Result<Expr, ParseError> {
+        self.assignment()
     }
 
-    fn equality(&mut self) -> Expr {
-        let mut expr = self.comparison();
+    fn assignment(&mut self) -> Result<Expr, ParseError> {
+        let expr = self.equality()?;
+
+        if self.match_token(&Equal) {
+            let equals = self.previous().clone();
+            let value = self.assignment()?;
+            match expr {
+                Variable { name, span } => Ok(Assign {
+                    name,
+                    span: span.to(value.span()),
+                    value: Box::from(value),
+                }),
+                Get { object, name, span } => Ok(Set {
+                    object,
+                    name,
+                    span: span.to(value.span()),
+                    value: Box::from(value),
+                }),
+                // Not worth synchronizing for, the parser is not confused.
+                _ => {
+                    self.errors
+                        .push(ParseError::new(&equals, "Invalid assignment target"));
+                    Ok(expr)
+                }
+            }
+        } else {
+            Ok(expr)
+        }
+    }
+
+    fn equality(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.comparison()?;
 
         while self.match_tokens(&[BangEqual, EqualEqual]) {
-            let rhs = self.comparison();
-            let operator = self.previous();
+            let operator = self.previous().clone();
+            let rhs = self.comparison()?;
             expr = Expr::Binary {
+                span: expr.span().to(rhs.span()),
                 left: Box::from(expr),
                 operator,
                 right: Box::from(rhs),
             }
         }
-        expr
+        Ok(expr)
     }
-    fn comparison(&mut self) -> Expr {
-        let mut expr = self.term();
+    fn comparison(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.pipe()?;
 
         while self.match_tokens(&[Greater, GreaterEqual, Less, LessEqual]) {
-            let op = self.previous();
-            let rhs = self.term();
+            let op = self.previous().clone();
+            let rhs = self.pipe()?;
 
             expr = Binary {
+                span: expr.span().to(rhs.span()),
                 left: Box::from(expr),
                 operator: op,
                 right: Box::from(rhs),
             }
         }
-        expr
+        Ok(expr)
+    }
+    // Left associative, binding looser than arithmetic and tighter than
+    // comparisons: `a + 1 |> f() |> g() == b` is `g(f(a + 1)) == b`.
+    fn pipe(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        while self.match_token(&Pipe) {
+            let operator = self.previous().clone();
+            let right = self.call()?;
+            let span = expr.span().to(right.span());
+            expr = Expr::Pipe {
+                left: Box::new(expr),
+                operator,
+                right: Box::new(right),
+                span,
+            };
+        }
+        Ok(expr)
     }
-    fn term(&mut self) -> Expr {
-        let mut expr = self.factor();
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.factor()?;
 
         while self.match_tokens(&[Minus, Plus]) {
-            let op = self.previous();
-            let rhs = self.factor();
+            let op = self.previous().clone();
+            let rhs = self.factor()?;
             expr = Binary {
+                span: expr.span().to(rhs.span()),
                 left: Box::from(expr),
                 operator: op,
                 right: Box::from(rhs),
             };
         }
-        expr
+        Ok(expr)
     }
-    fn factor(&mut self) -> Expr {
-        let mut expr = self.unary();
+    fn factor(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
         while self.match_tokens(&[Slash, Star]) {
-            let op = self.previous();
-            let rhs = self.unary();
+            let op = self.previous().clone();
+            let rhs = self.unary()?;
             expr = Binary {
+                span: expr.span().to(rhs.span()),
                 left: Box::from(expr),
                 operator: op,
                 right: Box::from(rhs),
             }
         }
-        expr
+        Ok(expr)
     }
-    fn unary(&mut self) -> Expr {
-        let mut expr = self.unary();
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        // `-9223372036854775808` is one literal, the smallest Int: its
+        // magnitude alone doesn't fit.
+        let min_magnitude = Some(scanner::LiteralValue::IntValue(i64::MIN));
+        if self.check(&Minus)
+            && self
+                .tokens
+                .get(self.current + 1)
+                .is_some_and(|token| token.literal == min_magnitude)
+        {
+            let minus = self.advance().clone();
+            let number = self.advance().clone();
+            return Ok(Literal {
+                value: LiteralValue::Int(i64::MIN),
+                span: minus.span.to(number.span),
+            });
+        }
         if self.match_tokens(&[Bang, Minus]) {
-            let op = self.previous();
-            let rhs = self.unary();
-            Unary {
+            let op = self.previous().clone();
+            let rhs = self.unary()?;
+            Ok(Unary {
+                span: op.span.to(rhs.span()),
                 operator: op,
                 right: Box::from(rhs),
+            })
+        } else if self.match_token(&CONSUME) {
+            let keyword = self.previous().clone();
+            let name = self.consume(Identifier, "Expected variable name after 'consume'")?;
+            Ok(Consume {
+                span: keyword.span.to(name.span),
+                keyword,
+                name,
+            })
+        } else {
+            self.call()
+        }
+    }
+    fn call(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.primary()?;
+
+        loop {
+            if self.match_token(&LeftParen) {
+                expr = self.finish_call(expr)?;
+            } else if self.match_token(&Dot) {
+                let name = self.consume(Identifier, "Expected property name after '.'")?;
+                expr = Get {
+                    span: expr.span().to(name.span),
+                    object: Box::from(expr),
+                    name,
+                };
+            } else {
+                break;
+            }
+        }
+        Ok(expr)
+    }
+    fn finish_call(&mut self, callee: Expr) -> Result<Expr, ParseError> {
+        let mut arguments = vec![];
+        if !self.check(&RightParen) {
+            loop {
+                arguments.push(self.expression()?);
+                if !self.match_token(&Comma) {
+                    break;
+                }
+            }
+        }
+        let paren = self.consume(RightParen, "Expected ')' after arguments")?;
+
+        Ok(Call {
+            span: callee.span().to(paren.span),
+            callee: Box::from(callee),
+            paren,
+            arguments,
+        })
+    }
+    fn primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.peek().clone();
+        if self.match_token(&LeftParen) {
+            self.tuple_or_grouping()
+        } else if self.match_token(&LeftBrace) {
+            self.record()
+        } else if self.match_token(&MATCH) {
+            self.match_expression()
+        } else if self.match_token(&Interpolation) {
+            self.interpolation()
+        } else if self.match_token(&SELF) {
+            Ok(SelfRef {
+                span: token.span,
+                keyword: token,
+            })
+        } else if self.match_token(&Identifier) {
+            Ok(Variable {
+                span: token.span,
+                name: token,
+            })
+        } else if self.match_tokens(&[Number, String, True, False, Nil]) {
+            if token.literal == Some(scanner::LiteralValue::IntValue(i64::MIN)) {
+                return Err(ParseError::new(
+                    &token,
+                    &format!("Integer literal {} does not fit in an Int", token.lexeme),
+                ));
+            }
+            let span = token.span;
+            match LiteralValue::from_token(token) {
+                Ok(value) => Ok(Literal { value, span }),
+                Err(msg) => Err(ParseError::new(self.previous(), &msg)),
             }
         } else {
-            self.primary()
+            Err(ParseError::new(&token, "Expected expression"))
+        }
+    }
+    // `"Age: ${age}!"` becomes `"Age: " + String.from(age) + "!"`, the
+    // Interpolation token before `${` is already consumed.
+    fn interpolation(&mut self) -> Result<Expr, ParseError> {
+        let start = self.previous().clone();
+        let mut parts = vec![];
+        let mut part = start.clone();
+        loop {
+            if let Some(scanner::LiteralValue::StringValue(text)) = &part.literal {
+                if !text.is_empty() || parts.is_empty() {
+                    parts.push(Literal {
+                        value: LiteralValue::StringValue(text.clone()),
+                        span: part.span,
+                    });
+                }
+            }
+            if part.token_type == InterpolationEnd {
+                break;
+            }
+            let value = self.expression()?;
+            parts.push(to_string_call(value, &part));
+            if !self.match_token(&InterpolationMiddle) {
+                self.consume(
+                    InterpolationEnd,
+                    "Expected '}' after interpolated expression",
+                )?;
+            }
+            part = self.previous().clone();
         }
+
+        let span = start.span.to(part.span);
+        let plus = Token::new(Plus, "+".to_string(), None, span);
+        let mut parts = parts.into_iter();
+        let first = parts.next().unwrap();
+        Ok(parts.fold(first, |left, right| Binary {
+            left: Box::from(left),
+            operator: plus.clone(),
+            right: Box::from(right),
+            span,
+        }))
     }
-    fn primary(&mut self) -> Expr {
-        let token = self.peek();
+    // `()` and `(a,)` are tuples, `(a)` only groups.
+    fn tuple_or_grouping(&mut self) -> Result<Expr, ParseError> {
+        let start = self.previous().span;
+        if self.match_token(&RightParen) {
+            return Ok(Tuple {
+                elements: vec![],
+                span: start.to(self.previous().span),
+            });
+        }
+
+        let first = self.expression()?;
+        if !self.match_token(&Comma) {
+            self.consume(RightParen, "Expected ')' after expression")?;
+            return Ok(Grouping {
+                expression: Box::from(first),
+                span: start.to(self.previous().span),
+            });
+        }
+        let mut elements = vec![first];
+        while !self.check(&RightParen) {
+            elements.push(self.expression()?);
+            if !self.match_token(&Comma) {
+                break;
+            }
+        }
+        self.consume(RightParen, "Expected ')' after tuple elements")?;
+        Ok(Tuple {
+            elements,
+            span: start.to(self.previous().span),
+        })
+    }
+    // `{name: value, age}`, where `age` is short for `age: age`.
+    fn record(&mut self) -> Result<Expr, ParseError> {
+        let start = self.previous().span;
+        let mut fields = vec![];
+        while !self.check(&RightBrace) {
+            let name = self.consume(Identifier, "Expected field name")?;
+            let value = if self.match_token(&Colon) {
+                self.expression()?
+            } else {
+                Variable {
+                    span: name.span,
+                    name: name.clone(),
+                }
+            };
+            fields.push((name, value));
+            if !self.match_token(&Comma) {
+                break;
+            }
+        }
+        self.consume(RightBrace, "Expected '}' after record fields")?;
+        Ok(Record {
+            fields,
+            span: start.to(self.previous().span),
+        })
+    }
+    fn match_expression(&mut self) -> Result<Expr, ParseError> {
+        let keyword = self.previous().clone();
+        let value = self.expression()?;
+        self.consume(LeftBrace, "Expected '{' after match value")?;
+
+        let mut arms = vec![];
+        while !self.check(&RightBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            let guard = if self.match_token(&IF) {
+                Some(self.expression()?)
+            } else {
+                None
+            };
+            self.consume(FatArrow, "Expected '=>' after pattern")?;
+
+            // A block body needs no ',' after it.
+            let body = if self.match_token(&LeftBrace) {
+                let start = self.previous().span;
+                let statements = self.block()?;
+                self.match_token(&Comma);
+                Stmt::Block {
+                    statements,
+                    span: start.to(self.previous().span),
+                }
+            } else {
+                let expression = self.expression()?;
+                if !self.check(&RightBrace) {
+                    self.consume(Comma, "Expected ',' after match arm")?;
+                }
+                Stmt::Expression {
+                    span: expression.span(),
+                    expression,
+                }
+            };
+            arms.push(MatchArm {
+                span: pattern.span().to(body.span()),
+                pattern,
+                guard,
+                body: Box::new(body),
+            });
+        }
+        self.consume(RightBrace, "Expected '}' after match arms")?;
+
+        Ok(Match {
+            span: keyword.span.to(self.previous().span),
+            keyword,
+            value: Box::new(value),
+            arms,
+        })
+    }
+    fn pattern(&mut self) -> Result<Pattern, ParseError> {
+        let pattern = self.pattern_primary()?;
+        if !self.match_token(&Colon) {
+            return Ok(pattern);
+        }
+        let type_expr = self.type_annotation("Expected type after ':' in pattern")?;
+        Ok(Pattern::Typed {
+            span: pattern.span().to(type_expr.span()),
+            pattern: Box::new(pattern),
+            type_expr,
+        })
+    }
+    fn pattern_primary(&mut self) -> Result<Pattern, ParseError> {
+        let token = self.peek().clone();
+        if self.match_token(&Identifier) {
+            if token.lexeme == "_" {
+                return Ok(Pattern::Wildcard { span: token.span });
+            }
+            return Ok(Pattern::Binding {
+                span: token.span,
+                name: token,
+            });
+        }
+        if self.match_tokens(&[Number, String, True, False, Nil]) {
+            if token.literal == Some(scanner::LiteralValue::IntValue(i64::MIN)) {
+                return Err(ParseError::new(
+                    &token,
+                    &format!("Integer literal {} does not fit in an Int", token.lexeme),
+                ));
+            }
+            let value = LiteralValue::from_token(token.clone())
+                .map_err(|msg| ParseError::new(&token, &msg))?;
+            return Ok(Pattern::Literal {
+                value,
+                span: token.span,
+            });
+        }
+        if self.match_token(&Minus) {
+            let number = self.consume(Number, "Expected number after '-' in pattern")?;
+            // Only the magnitude of the smallest Int, scanned as that Int as
+            // in `unary`, has no negation.
+            let value = match LiteralValue::from_token(number.clone()) {
+                Ok(LiteralValue::Int(n)) => LiteralValue::Int(n.checked_neg().unwrap_or(i64::MIN)),
+                Ok(LiteralValue::Float(n)) => LiteralValue::Float(-n),
+                _ => return Err(ParseError::new(&number, "Expected number")),
+            };
+            return Ok(Pattern::Literal {
+                value,
+                span: token.span.to(number.span),
+            });
+        }
         if self.match_token(&LeftParen) {
-            let expr = self.expression();
-            self.consume(RightParen, "Expected ')'");
-            Grouping {
-                expression: Box::from(expr),
+            let mut elements = vec![];
+            let mut tuple = true;
+            if !self.check(&RightParen) {
+                elements.push(self.pattern()?);
+                tuple = self.check(&Comma);
+                while self.match_token(&Comma) && !self.check(&RightParen) {
+                    elements.push(self.pattern()?);
+                }
+            }
+            self.consume(RightParen, "Expected ')' after tuple pattern")?;
+            if !tuple {
+                return Ok(elements.pop().unwrap());
+            }
+            return Ok(Pattern::Tuple {
+                elements,
+                span: token.span.to(self.previous().span),
+            });
+        }
+        if self.match_token(&LeftBrace) {
+            let mut fields = vec![];
+            while !self.check(&RightBrace) {
+                let name = self.consume(Identifier, "Expected field name in record pattern")?;
+                let pattern = if self.match_token(&Colon) {
+                    self.pattern()?
+                } else {
+                    Pattern::Binding {
+                        span: name.span,
+                        name: name.clone(),
+                    }
+                };
+                fields.push((name, pattern));
+                if !self.match_token(&Comma) {
+                    break;
+                }
             }
+            self.consume(RightBrace, "Expected '}' after record pattern")?;
+            return Ok(Pattern::Record {
+                fields,
+                span: token.span.to(self.previous().span),
+            });
+        }
+        Err(ParseError::new(&token, "Expected pattern"))
+    }
+    fn consume(&mut self, token_type: TokenType, msg: &str) -> Result<Token, ParseError> {
+        if self.check(&token_type) {
+            Ok(self.advance().clone())
         } else {
-            Literal {
-                value: LiteralValue::from_token(token),
+            Err(ParseError::new(self.peek(), msg))
+        }
+    }
+    // Discards tokens until it reaches a likely statement boundary.
+    fn synchronize(&mut self) {
+        self.advance();
+
+        while !self.is_at_end() {
+            if self.previous().token_type == Semicolon {
+                return;
             }
+            match self.peek().token_type {
+                ACTOR | BE | FUN | LET | RETURN | LeftBrace | RightBrace => return,
+                _ => (),
+            }
+            self.advance();
         }
     }
-    fn consume(&mut self, token_type: TokenType, msg: &str) {
-        let token = self.peek();
-        if token.token_type == token_type {
+    // Like `synchronize`, for an actor member: skips to the next member or
+    // the end of the actor body, over any braces the broken member opened.
+    fn synchronize_member(&mut self) {
+        let mut depth = 0;
+        while !self.is_at_end() {
+            match self.peek().token_type {
+                LET | FUN | BE | RightBrace if depth == 0 => return,
+                LeftBrace => depth += 1,
+                RightBrace => depth -= 1,
+                _ => (),
+            }
             self.advance();
-        } else {
-            panic!("{}", msg);
         }
     }
-    fn match_token(&self, typ: &TokenType) -> bool {
+    fn check(&self, typ: &TokenType) -> bool {
+        self.peek().token_type == *typ
+    }
+    fn match_token(&mut self, typ: &TokenType) -> bool {
         if self.is_at_end() {
             false
+        } else if self.check(typ) {
+            self.advance();
+            true
         } else {
-            if self.peek().token_type == *typ {
-                self.advance();
-                true
-            } else {
-                false
-            }
+            false
         }
     }
     fn match_tokens(&mut self, typs: &[TokenType]) -> bool {
-        todo!()
+        for typ in typs {
+            if self.match_token(typ) {
+                return true;
+            }
+        }
+        false
     }
     fn advance(&mut self) -> &Token {
         if !self.is_at_end() {
@@ -150,3 +912,405 @@ impl Parser {
         self.peek().token_type == EOF
     }
 }
+
+// `String.from(value)`, for a value interpolated after `part`.
+fn to_string_call(value: Expr, part: &Token) -> Expr {
+    let span = value.span();
+    let token = |token_type, lexeme: &str| Token::new(token_type, lexeme.to_string(), None, span);
+    let module = Variable {
+        name: token(Identifier, "String"),
+        span,
+    };
+    Call {
+        callee: Box::from(Get {
+            object: Box::from(module),
+            name: token(Identifier, "from"),
+            span,
+        }),
+        paren: Token::new(RightParen, ")".to_string(), None, part.span),
+        arguments: vec![value],
+        span,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(source: &str) -> Result<Vec<Stmt>, Vec<ParseError>> {
+        let mut scanner = Scanner::new(source);
+        let tokens = scanner.scan_tokens().unwrap();
+        Parser::new(tokens).parse()
+    }
+
+    #[test]
+    fn parse_statements() {
+        let statements = parse("let mut a = 1; { a = a + 2; } a").unwrap();
+        assert_eq!(statements.len(), 3);
+    }
+
+    #[test]
+    fn nodes_carry_spans() {
+        let statements = parse("let a = 1;\n{ a = (a + 2) * 3; }").unwrap();
+        assert_eq!(statements[0].span(), Span::new(1, 1, 0, 10));
+        assert_eq!(statements[1].span(), Span::new(2, 1, 11, 31));
+
+        let expression = match &statements[1] {
+            Stmt::Block { statements, .. } => match &statements[0] {
+                Stmt::Expression { expression, .. } => expression.clone(),
+                _ => panic!("Expected an expression statement"),
+            },
+            _ => panic!("Expected a block"),
+        };
+        assert_eq!(expression.span(), Span::new(2, 3, 13, 28));
+        match expression {
+            Assign { value, .. } => assert_eq!(value.span(), Span::new(2, 7, 17, 28)),
+            _ => panic!("Expected an assignment"),
+        }
+    }
+
+    #[test]
+    fn parse_actor_declaration() {
+        let source = "actor Main {
+            let mut age = 23;
+
+            be update_age(by_how_much: Int) {
+                self.updating_age = by_how_much;
+            }
+            fn updating_age(by: Int, times) {
+                self.age = self.age + by;
+            }
+        }";
+        let statements = parse(source).unwrap();
+        assert_eq!(statements.len(), 1);
+        assert_eq!(
+            statements[0].to_string(),
+            "(actor Main (let mut age 23) \
+             (be update_age (by_how_much: Int) (= (. self updating_age) by_how_much)) \
+             (fn updating_age (by: Int times) (= (. self age) (+ (. self age) by))))"
+        );
+    }
+
+    #[test]
+    fn parse_calls() {
+        let statements = parse("counter.add(1, 2 + 3)(4); spawn()").unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(call (call (. counter add) 1 (+ 2 3)) 4)"
+        );
+        assert_eq!(statements[1].to_string(), "(call spawn)");
+
+        let errors = parse("f(1, 2;").unwrap_err();
+        assert_eq!(errors[0].message, "Expected ')' after arguments");
+    }
+
+    #[test]
+    fn parse_pipes() {
+        let statements = parse(
+            "let newly = by
+                |> String.trim_string()
+                |> String.parse_to_int();
+            a + 1 |> f(2) |> g == b;",
+        )
+        .unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(let newly (|> (|> by (call (. String trim_string))) (call (. String parse_to_int))))"
+        );
+        assert_eq!(
+            statements[1].to_string(),
+            "(== (|> (|> (+ a 1) (call f 2)) g) b)"
+        );
+    }
+
+    #[test]
+    fn interpolation_desugars_to_concatenation() {
+        let statements = parse(r#""Age: ${self.age}!"; "${a}${b}";"#).unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(+ (+ \"Age: \" (call (. String from) (. self age))) \"!\")"
+        );
+        assert_eq!(
+            statements[1].to_string(),
+            "(+ (+ \"\" (call (. String from) a)) (call (. String from) b))"
+        );
+
+        let errors = parse(r#""a ${1 +}";"#).unwrap_err();
+        assert_eq!(errors[0].message, "Expected expression");
+    }
+
+    #[test]
+    fn parse_tuples_and_records() {
+        let statements = parse("(); (1,); (1, \"a\", b); (1); ({name: \"lok\", age});").unwrap();
+        let printed: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            printed,
+            [
+                "(tuple)",
+                "(tuple 1)",
+                "(tuple 1 \"a\" b)",
+                "(group 1)",
+                "(group (record (name \"lok\") (age age)))"
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_match() {
+        let source = "match value {
+            0 => \"zero\",
+            -1 | _ if value < 0 => \"negative\",
+            n: Int if n > 100 => { big(n) }
+            (a, _) => a,
+            {name, age: 30} => name,
+            s: (String | Nil) => s,
+            _ => \"other\"
+        }
+        print(1);";
+        let errors = parse(source).unwrap_err();
+        assert_eq!(errors[0].message, "Expected '=>' after pattern");
+
+        let statements = parse(&source.replace("-1 | _", "_")).unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(match value \
+             (arm 0 \"zero\") \
+             (arm _ (if (< value 0)) \"negative\") \
+             (arm n: Int (if (> n 100)) (block (call big n))) \
+             (arm (a, _) a) \
+             (arm {name, age: 30} name) \
+             (arm s: (String | Nil) s) \
+             (arm _ \"other\"))"
+        );
+        assert_eq!(statements[1].to_string(), "(call print 1)");
+    }
+
+    #[test]
+    fn parse_function_declaration() {
+        let source = "fn add(a: Int, b) -> Int {
+            fn inner() { return; }
+            return a + b;
+        }";
+        let statements = parse(source).unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(fn add (a: Int b) -> Int (fn inner () (return)) (return (+ a b)))"
+        );
+        assert_eq!(statements[0].span().end, source.len());
+    }
+
+    #[test]
+    fn overloaded_methods_must_differ() {
+        let errors = parse(
+            "actor A {
+                fn f(x: Int) {}
+                fn f(x: String) {}
+                fn f(y: Int) {}
+                be f(x: Bool) {}
+            }",
+        )
+        .unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "'f' is already declared with the same parameter types",
+                "'f' is declared both as fn and be"
+            ]
+        );
+        assert_eq!(errors[0].span.line, 4);
+    }
+
+    #[test]
+    fn parse_type_annotations() {
+        let statements = parse(
+            "let x: Int = 1;
+            let mut pair: (Int, (String | Nil)) = nil;
+            let unit: () = nil;
+            let one: (Int,) = nil;
+            fn f(a: Int | String | Bool, b: ((Int | String) | Bool)) {}",
+        )
+        .unwrap();
+        let printed: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            printed,
+            [
+                "(let x: Int 1)",
+                "(let mut pair: (Int, (String | Nil)) nil)",
+                "(let unit: () nil)",
+                "(let one: (Int,) nil)",
+                "(fn f (a: (Int | String | Bool) b: (Int | String | Bool)))",
+            ]
+        );
+
+        let errors = parse("let x: = 1;").unwrap_err();
+        assert_eq!(errors[0].message, "Expected variable type after ':'");
+        let errors = parse("let x: (Int | ) = 1;").unwrap_err();
+        assert_eq!(errors[0].message, "Expected type");
+        let errors = parse("fn f(a: (Int | String {}").unwrap_err();
+        assert_eq!(errors[0].message, "Expected ')' after type");
+    }
+
+    #[test]
+    fn parse_capabilities_and_consume() {
+        let statements = parse(
+            "let s: String iso = \"a\";
+            let box = consume s;
+            be send(to: Log tag, line: (String val | Nil)) {}",
+        );
+        let errors = statements.unwrap_err();
+        assert_eq!(errors.len(), 1);
+
+        let statements = parse(
+            "let s: String iso = \"a\";
+            let box: String box = consume s;
+            fn send(to: Log tag, line: (String val | Nil)) {}",
+        )
+        .unwrap();
+        let printed: Vec<String> = statements.iter().map(|s| s.to_string()).collect();
+        assert_eq!(
+            printed,
+            [
+                "(let s: String iso \"a\")",
+                "(let box: String box (consume s))",
+                "(fn send (to: Log tag line: (String val | Nil)))",
+            ]
+        );
+
+        let errors = parse("consume 1;").unwrap_err();
+        assert_eq!(errors[0].message, "Expected variable name after 'consume'");
+    }
+
+    #[test]
+    fn parse_design_doc_actor() {
+        let source = "actor Main {
+            let mut age = 23;
+
+            be update_age(by_how_much: (Int | String)) {
+               self.updating_age(by_how_much)
+            }
+            fn updating_age(by: Int) {
+                self.age = self.age + by;
+            }
+            fn updating_age(by: String) {
+                self.age = self.age + 1;
+            }
+        }";
+        let statements = parse(source).unwrap();
+        assert!(statements[0]
+            .to_string()
+            .contains("(be update_age (by_how_much: (Int | String))"));
+    }
+
+    #[test]
+    fn return_outside_function_is_an_error() {
+        let errors = parse("return 1;").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Cannot return from top-level code");
+    }
+
+    #[test]
+    fn actor_body_only_accepts_members() {
+        let errors = parse("actor Main { 1 + 2; }").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Expected 'let', 'fn' or 'be' in actor body"
+        );
+
+        let errors = parse("actor Main { be go(x: ) {} }").unwrap_err();
+        assert_eq!(errors[0].message, "Expected parameter type after ':'");
+    }
+
+    #[test]
+    fn actor_members_after_an_error_stay_in_the_actor() {
+        let errors = parse("actor A { let = 1; be go() {} fn f() {} }").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Expected variable name after 'let'");
+
+        let errors = parse(
+            "actor A { fn 1() { let a = 1; } be go() {} 1 + 2; be stop() {} }
+let = 2;",
+        )
+        .unwrap_err();
+        let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Expected fn name",
+                "Expected 'let', 'fn' or 'be' in actor body",
+                "Expected variable name after 'let'"
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_expression_is_an_error() {
+        let errors = parse("let a = ;").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Expected expression");
+        assert_eq!(errors[0].span, Span::new(1, 9, 8, 9));
+    }
+
+    #[test]
+    fn reports_every_error_in_one_run() {
+        let source = "let a = (1 + 2;\nlet = 3;\nlet b = 4;\n1 + * 2;";
+        let errors = parse(source).unwrap_err();
+        assert_eq!(errors.len(), 3);
+
+        assert_eq!(errors[0].message, "Expected ')' after expression");
+        assert_eq!((errors[0].span.line, errors[0].span.column), (1, 15));
+        assert_eq!(errors[1].message, "Expected variable name after 'let'");
+        assert_eq!((errors[1].span.line, errors[1].span.column), (2, 5));
+        assert_eq!(errors[2].message, "Expected expression");
+        assert_eq!((errors[2].span.line, errors[2].span.column), (4, 5));
+    }
+
+    #[test]
+    fn errors_inside_blocks_keep_parsing_the_block() {
+        let errors = parse("{ let = 1; let b = ; }").unwrap_err();
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn invalid_assignment_target() {
+        let errors = parse("1 + 2 = 3;").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Invalid assignment target");
+        assert_eq!(
+            errors[0].to_string(),
+            "[line 1:7] Error at '=': Invalid assignment target"
+        );
+    }
+
+    #[test]
+    fn smallest_int_is_one_literal() {
+        let statements = parse("-9223372036854775808; --9223372036854775808;").unwrap();
+        assert_eq!(statements[0].to_string(), "-9223372036854775808");
+        assert_eq!(statements[1].to_string(), "(- -9223372036854775808)");
+        assert_eq!(statements[0].span(), Span::new(1, 1, 0, 21));
+
+        for source in ["9223372036854775808;", "1 - 9223372036854775808;"] {
+            let errors = parse(source).unwrap_err();
+            assert_eq!(
+                errors[0].message,
+                "Integer literal 9223372036854775808 does not fit in an Int"
+            );
+        }
+    }
+
+    #[test]
+    fn smallest_int_pattern_is_one_literal() {
+        let statements = parse("match x { -9223372036854775808 => 1, -3 => 2, _ => 3 }").unwrap();
+        assert_eq!(
+            statements[0].to_string(),
+            "(match x (arm -9223372036854775808 1) (arm -3 2) (arm _ 3))"
+        );
+
+        let errors = parse("match x { 9223372036854775808 => 1, _ => 2 }").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Integer literal 9223372036854775808 does not fit in an Int"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use crate::expr::LiteralValue;
+use crate::scanner::{Span, Token};
+use crate::type_expr::TypeExpr;
+
+use std::fmt;
+
+// The left-hand side of a `match` arm:
+//
+//   1                  a literal such as `1`, `"a"` or `nil`, matches an
+//                      equal value
+//   _                  wildcard, matches anything
+//   name               binding, matches anything and names it
+//   name: Int          type pattern, matches values of the type
+//   (a, _)             tuple, matches tuples of the same length
+//   {name, age: 1}     record, matches records having the fields
+#[derive(Debug, Clone)]
+pub enum Pattern {
+    Literal {
+        value: LiteralValue,
+        span: Span,
+    },
+    Wildcard {
+        span: Span,
+    },
+    Binding {
+        name: Token,
+        span: Span,
+    },
+    Typed {
+        pattern: Box<Pattern>,
+        type_expr: TypeExpr,
+        span: Span,
+    },
+    Tuple {
+        elements: Vec<Pattern>,
+        span: Span,
+    },
+    // `{name}` is short for `{name: name}`.
+    Record {
+        fields: Vec<(Token, Pattern)>,
+        span: Span,
+    },
+}
+
+impl Pattern {
+    pub fn span(&self) -> Span {
+        match self {
+            Pattern::Literal { span, .. }
+            | Pattern::Wildcard { span }
+            | Pattern::Binding { span, .. }
+            | Pattern::Typed { span, .. }
+            | Pattern::Tuple { span, .. }
+            | Pattern::Record { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Pattern::Literal {
+                value: LiteralValue::StringValue(s),
+                ..
+            } => write!(f, "{:?}", s),
+            Pattern::Literal { value, .. } => write!(f, "{}", value),
+            Pattern::Wildcard { .. } => write!(f, "_"),
+            Pattern::Binding { name, .. } => write!(f, "{}", name.lexeme),
+            Pattern::Typed {
+                pattern, type_expr, ..
+            } => write!(f, "{}: {}", pattern, type_expr),
+            Pattern::Tuple { elements, .. } => {
+                let elements: Vec<String> = elements.iter().map(|p| p.to_string()).collect();
+                write!(f, "({})", elements.join(", "))
+            }
+            Pattern::Record { fields, .. } => {
+                let fields: Vec<String> = fields
+                    .iter()
+                    .map(|(name, pattern)| match pattern {
+                        Pattern::Binding { name: binding, .. } if binding.lexeme == name.lexeme => {
+                            name.lexeme.clone()
+                        }
+                        pattern => format!("{}: {}", name.lexeme, pattern),
+                    })
+                    .collect();
+                write!(f, "{{{}}}", fields.join(", "))
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use crate::ast_printer::print_ast;
+use crate::checker::{Checker, Type};
+use crate::cst::SyntaxTree;
+use crate::diagnostics::Diagnostic;
+use crate::expr::Expr;
+use crate::interpreter::{Interpreter, Value};
+use crate::scanner::{Scanner, TokenType};
+use crate::stmt::Stmt;
+use crate::{
+    front_end_of, read, render, token_listing, Failure, Options, EX_DATAERR, EX_IOERR, EX_SOFTWARE,
+    EX_USAGE,
+};
+
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use std::env;
+use std::path::PathBuf;
+
+const PROMPT: &str = "> ";
+const CONTINUATION_PROMPT: &str = "... ";
+const FILE: &str = "<repl>";
+
+const HELP: &str = ":type <expr>     show the type of an expression
+:ast <code>      show the syntax tree of code, see --ast-format
+:tokens <code>   show the tokens of code
+:load <file>     run a file in the session
+:reset           forget every binding
+:help            show this
+";
+
+// The checker and the interpreter of a REPL, kept from one entry to the next
+// so later lines see the bindings of earlier ones.
+pub struct Session {
+    checker: Checker,
+    interpreter: Interpreter,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self::with_interpreter(Interpreter::new())
+    }
+
+    fn with_interpreter(interpreter: Interpreter) -> Self {
+        Self {
+            checker: Checker::new(),
+            interpreter,
+        }
+    }
+
+    // Runs one entry and returns the value of its last expression. The `;`
+    // after a final expression may be left out.
+    pub fn eval(&mut self, entry: &str, options: &Options) -> Result<Value, Failure> {
+        let mut tree = SyntaxTree::scan(entry);
+        tree.imply_semicolon();
+        self.run(tree, entry, FILE, options)
+    }
+
+    fn run(
+        &mut self,
+        tree: SyntaxTree,
+        source: &str,
+        file: &str,
+        options: &Options,
+    ) -> Result<Value, Failure> {
+        let statements = front_end_of(tree, source, file, options, &mut self.checker)?;
+        self.interpreter.interpret(&statements).map_err(|errors| {
+            let diagnostics: Vec<Diagnostic> = errors.iter().map(Diagnostic::from).collect();
+            Failure::new(
+                EX_SOFTWARE,
+                render(&diagnostics, source, file, options.format),
+            )
+        })
+    }
+
+    // `:name argument`, returns what to print.
+    pub fn meta_command(&mut self, entry: &str, options: &Options) -> Result<String, Failure> {
+        let entry = entry.trim();
+        let (name, argument) = entry
+            .split_once(char::is_whitespace)
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((entry, ""));
+        match (name, argument) {
+            (":type", expr) if !expr.is_empty() => {
+                match parse(expr, options)?.as_slice() {
+                    // A clone, so `:type consume s` doesn't consume `s`.
+                    [Stmt::Expression { expression, .. }] => self
+                        .checker
+                        .clone()
+                        .type_of(expression)
+                        .map(|found| match found {
+                            Type::Unknown => unknown_type(expression),
+                            found => format!("{}\n", found),
+                        })
+                        .map_err(|errors| {
+                            let diagnostics: Vec<Diagnostic> =
+                                errors.iter().map(Diagnostic::from).collect();
+                            Failure::new(
+                                EX_DATAERR,
+                                render(&diagnostics, expr, FILE, options.format),
+                            )
+                        }),
+                    _ => Err(Failure::new(
+                        EX_USAGE,
+                        "Usage: :type <expression>\n".to_string(),
+                    )),
+                }
+            }
+            (":ast", code) if !code.is_empty() => {
+                let statements = parse(code, options)?;
+                Ok(print_ast(&statements, options.ast_format))
+            }
+            (":tokens", code) if !code.is_empty() => match SyntaxTree::parse(code) {
+                Ok(tree) => Ok(token_listing(tree.tokens())),
+                Err(errors) => {
+                    let diagnostics: Vec<Diagnostic> =
+                        errors.iter().map(Diagnostic::from).collect();
+                    Err(Failure::new(
+                        EX_DATAERR,
+                        render(&diagnostics, code, FILE, options.format),
+                    ))
+                }
+            },
+            (":load", path) if !path.is_empty() => {
+                let content = read(path)?;
+                match self.run(SyntaxTree::scan(&content), &content, path, options)? {
+                    Value::Nil => Ok(String::new()),
+                    value => Ok(format!("{}\n", value)),
+                }
+            }
+            (":reset", "") => {
+                *self = Self::with_interpreter(Interpreter::with_output(self.interpreter.output()));
+                Ok(String::new())
+            }
+            (":help", "") => Ok(HELP.to_string()),
+            (":type" | ":ast" | ":tokens" | ":load" | ":reset" | ":help", _) => Err(Failure::new(
+                EX_USAGE,
+                format!("Wrong arguments to {}, see :help\n", name),
+            )),
+            _ => Err(Failure::new(
+                EX_USAGE,
+                format!("Unknown command {}, see :help\n", name),
+            )),
+        }
+    }
+}
+
+// Reads entries until end of input. Errors are printed and the session goes
+// on, Ctrl-C drops the entry being typed.
+pub fn run_repl(options: &Options) -> Result<(), Failure> {
+    let mut editor = DefaultEditor::new().map_err(|err| {
+        Failure::new(
+            EX_IOERR,
+            format!("Could not start the line editor: {}\n", err),
+        )
+    })?;
+    let history = history_path();
+    if let Some(path) = &history {
+        // There is no history before the first session.
+        let _ = editor.load_history(path);
+    }
+
+    let mut session = Session::new();
+    let mut entry = String::new();
+    loop {
+        let prompt = if entry.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => {
+                entry.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                return Err(Failure::new(
+                    EX_IOERR,
+                    format!("Could not read the line: {}\n", err),
+                ))
+            }
+        };
+
+        // An empty line ends an entry that would otherwise never be complete.
+        if !line.trim().is_empty() {
+            entry.push_str(&line);
+            entry.push('\n');
+            if is_incomplete(&entry) {
+                continue;
+            }
+        } else if entry.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(entry.trim_end());
+        if entry.starts_with(':') {
+            match session.meta_command(&entry, options) {
+                Ok(output) => print!("{}", output),
+                Err(err) => eprint!("{}", err.message),
+            }
+        } else {
+            match session.eval(&entry, options) {
+                Ok(Value::Nil) => (),
+                Ok(value) => println!("{}", value),
+                Err(err) => eprint!("{}", err.message),
+            }
+        }
+        entry.clear();
+    }
+
+    if let Some(path) = &history {
+        if let Err(err) = editor.save_history(path) {
+            eprintln!("Could not save the history to {}: {}", path.display(), err);
+        }
+    }
+    Ok(())
+}
+
+// `$LOKLANG_HISTORY`, or `.loklang_history` in the home directory.
+fn history_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("LOKLANG_HISTORY") {
+        return Some(PathBuf::from(path));
+    }
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".loklang_history"))
+}
+
+// An entry goes on over the next line while a paren, brace or block comment
+// is left open. A string still open at the end of a line is an error, not a
+// string the next line goes on.
+fn is_incomplete(entry: &str) -> bool {
+    match Scanner::new(entry).scan_tokens() {
+        Ok(tokens) => {
+            let mut depth = 0;
+            for token in &tokens {
+                match token.token_type {
+                    TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+                    TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+                    _ => (),
+                }
+            }
+            depth > 0
+        }
+        Err(errors) => errors
+            .iter()
+            .any(|err| err.message.starts_with("Unterminated block comment")),
+    }
+}
+
+// Said in words, a lone `_` reads like a bug. Types are only unknown where
+// nothing declares them, mostly calls to a fn without a `->` return type.
+fn unknown_type(expr: &Expr) -> String {
+    match expr {
+        Expr::Call { callee, .. } => match callee.as_ref() {
+            Expr::Variable { name, .. } | Expr::Get { name, .. } => {
+                format!("Type unknown, '{}' declares no return type\n", name.lexeme)
+            }
+            _ => "Type unknown, the callee declares no return type\n".to_string(),
+        },
+        _ => "Type unknown, nothing declares it\n".to_string(),
+    }
+}
+
+// Like an entry, `code` may leave out its final `;`.
+fn parse(source: &str, options: &Options) -> Result<Vec<Stmt>, Failure> {
+    let failure = |diagnostics: Vec<Diagnostic>| {
+        Failure::new(
+            EX_DATAERR,
+            render(&diagnostics, source, FILE, options.format),
+        )
+    };
+    let mut tree = SyntaxTree::parse(source)
+        .map_err(|errors| failure(errors.iter().map(Diagnostic::from).collect()))?;
+    tree.imply_semicolon();
+    tree.ast()
+        .map_err(|errors| failure(errors.iter().map(Diagnostic::from).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ast_printer::AstFormat;
+    use crate::diagnostics::ErrorFormat;
+    use std::sync::{Arc, Mutex};
+
+    fn options() -> Options {
+        Options {
+            format: ErrorFormat::Human,
+            emit: vec![],
+            ast_format: AstFormat::SExpr,
+        }
+    }
+
+    #[test]
+    fn continues_open_entries() {
+        assert!(is_incomplete("actor Main {\n"));
+        assert!(is_incomplete("print(1,\n"));
+        assert!(is_incomplete("/* a\n"));
+        assert!(!is_incomplete("let s = \"open\n"));
+        assert!(!is_incomplete("print(\"a ${b\n"));
+        assert!(!is_incomplete("actor Main {}\n"));
+        assert!(!is_incomplete("let a = 1;\n"));
+        // Too many closing braces can't be fixed by more lines.
+        assert!(!is_incomplete("}\n"));
+    }
+
+    #[test]
+    fn keeps_bindings_between_entries() {
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut session = Session::with_interpreter(Interpreter::with_output(output.clone()));
+        let options = options();
+
+        assert!(matches!(
+            session.eval("let mut a = 1;\n", &options),
+            Ok(Value::Nil)
+        ));
+        assert!(session.eval("a = a +;\n", &options).is_err());
+        assert!(session
+            .eval("fn twice(x: Int) -> Int {\nreturn x * 2;\n}\n", &options)
+            .is_ok());
+        assert!(matches!(
+            session.eval("twice(a + 1)\n", &options),
+            Ok(Value::Int(4))
+        ));
+        session.eval("print(a)\n", &options).ok();
+        assert_eq!(String::from_utf8_lossy(&output.lock().unwrap()), "1\n");
+    }
+
+    #[test]
+    fn overloads_join_across_entries() {
+        let mut session = Session::new();
+        let options = options();
+
+        for entry in [
+            "fn f(x: Int) -> Int { x }\n",
+            "fn f(x: String) -> String { x }\n",
+            "fn f(x: Int) -> Int { x + 1 }\n",
+        ] {
+            assert!(session.eval(entry, &options).is_ok());
+        }
+        assert!(matches!(
+            session.eval("f(1)\n", &options),
+            Ok(Value::Int(2))
+        ));
+        let mut types = |expr: &str| match session.meta_command(expr, &options) {
+            Ok(output) => output,
+            Err(err) => err.message,
+        };
+        assert_eq!(types(":type f(1)"), "Int\n");
+        assert_eq!(types(":type f(\"a\")"), "String\n");
+    }
+
+    #[test]
+    fn diagnostics_quote_the_entry() {
+        let mut session = Session::new();
+        let options = options();
+        let message = |result: Result<Value, Failure>| match result {
+            Ok(_) => panic!("Expected an error"),
+            Err(err) => err.message,
+        };
+
+        assert_eq!(
+            message(session.eval("1 +\n", &options)),
+            "error[E0002]: Expected expression\n \
+             --> <repl>:1:4\n  \
+             |\n\
+             1 | 1 +\n  \
+             |    ^\n"
+        );
+        assert_eq!(
+            message(session.eval("print(\"a)\n", &options)),
+            "error[E0001]: Unterminated string.\n \
+             --> <repl>:1:7\n  \
+             |\n\
+             1 | print(\"a)\n  \
+             |       ^^^\n"
+        );
+    }
+
+    #[test]
+    fn runs_meta_commands() {
+        let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+        let mut session = Session::with_interpreter(Interpreter::with_output(output.clone()));
+        let options = options();
+        let mut meta = |entry: &str| match session.meta_command(entry, &options) {
+            Ok(output) => output,
+            Err(err) => err.message,
+        };
+
+        let path = std::env::temp_dir().join("loklang_repl_load.lok");
+        std::fs::write(
+            &path,
+            "let s: String iso = \"lok\";\nprint(s);\n\
+             fn twice(x: Int) -> Int { return x * 2; }\n\
+             fn echo(x) { return x; }\n",
+        )
+        .unwrap();
+        assert_eq!(meta(&format!(":load {}", path.display())), "");
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(meta(":type (s, 1.5)"), "(String, Float)\n");
+        // The clone is consumed, not the session.
+        assert_eq!(meta(":type consume s"), "String\n");
+        assert_eq!(meta(":type s"), "String\n");
+        assert_eq!(meta(":type twice(1)"), "Int\n");
+        assert_eq!(
+            meta(":type echo(1)"),
+            "Type unknown, 'echo' declares no return type\n"
+        );
+        assert_eq!(
+            meta(":type (echo)(1)"),
+            "Type unknown, the callee declares no return type\n"
+        );
+        assert_eq!(meta(":ast -s"), "(- s)\n");
+        assert_eq!(
+            meta(":tokens s;"),
+            "1:1     Identifier          s\n1:2     Semicolon           ;\n1:3     EOF                 \n"
+        );
+        assert_eq!(meta(":type let a = 1;"), "Usage: :type <expression>\n");
+        assert_eq!(meta(":reset now"), "Wrong arguments to :reset, see :help\n");
+        assert_eq!(meta(":quit"), "Unknown command :quit, see :help\n");
+
+        assert_eq!(meta(":reset"), "");
+        assert!(meta(":type s").contains("Undefined variable 's'"));
+        assert_eq!(String::from_utf8_lossy(&output.lock().unwrap()), "lok\n");
+    }
+}
@@ -0,0 +1,56 @@
+use crate::runtime::Message;
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+// A FIFO queue of messages waiting to be processed by one actor. Any thread
+// may push, only the worker currently running the actor pops.
+#[derive(Default)]
+pub struct Mailbox {
+    messages: Mutex<VecDeque<Message>>,
+}
+
+impl Mailbox {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&self, message: Message) {
+        self.messages.lock().unwrap().push_back(message);
+    }
+
+    pub fn pop(&self) -> Option<Message> {
+        self.messages.lock().unwrap().pop_front()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.lock().unwrap().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::Value;
+
+    fn message(n: i64) -> Message {
+        Message {
+            behaviour: "go".to_string(),
+            arguments: vec![Value::Int(n)],
+        }
+    }
+
+    #[test]
+    fn messages_are_delivered_in_order() {
+        let mailbox = Mailbox::new();
+        assert!(mailbox.is_empty());
+
+        mailbox.push(message(1));
+        mailbox.push(message(2));
+        assert!(!mailbox.is_empty());
+
+        assert_eq!(mailbox.pop().unwrap().arguments, vec![Value::Int(1)]);
+        assert_eq!(mailbox.pop().unwrap().arguments, vec![Value::Int(2)]);
+        assert!(mailbox.pop().is_none());
+    }
+}
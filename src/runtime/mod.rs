@@ -0,0 +1,193 @@
+pub mod mailbox;
+pub mod scheduler;
+
+use crate::environment::Environment;
+use crate::interpreter::{Interpreter, RuntimeError, Value};
+use crate::scanner::Token;
+use crate::stmt::{Function, Stmt};
+
+use mailbox::Mailbox;
+use scheduler::Scheduler;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+// Where `print` writes to, stdout unless a test captures it.
+pub type Output = Arc<Mutex<dyn Write + Send>>;
+
+// The runtime representation of an `actor` declaration.
+#[derive(Debug)]
+pub struct ActorClass {
+    pub name: Token,
+    pub fields: Vec<Stmt>,
+    pub methods: Vec<Function>,
+}
+
+impl ActorClass {
+    // Every overload of method `name`, empty if there is none.
+    pub fn methods(&self, name: &str) -> Vec<&Function> {
+        self.methods
+            .iter()
+            .filter(|method| method.name.lexeme == name)
+            .collect()
+    }
+
+    // `None` if the actor has no such field.
+    pub fn field_is_mutable(&self, name: &str) -> Option<bool> {
+        self.fields.iter().find_map(|field| match field {
+            Stmt::Let {
+                name: field_name,
+                mutable,
+                ..
+            } if field_name.lexeme == name => Some(*mutable),
+            _ => None,
+        })
+    }
+}
+
+impl PartialEq for ActorClass {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+// A pending call of behaviour `behaviour` with already evaluated arguments.
+#[derive(Debug)]
+pub struct Message {
+    pub behaviour: String,
+    pub arguments: Vec<Value>,
+}
+
+pub struct Actor {
+    pub id: usize,
+    pub class: Arc<ActorClass>,
+    fields: Mutex<HashMap<String, Value>>,
+    mailbox: Mailbox,
+    // Set while the actor sits in a run queue or is being run by a worker.
+    scheduled: AtomicBool,
+}
+
+impl Actor {
+    pub fn field(&self, name: &str) -> Option<Value> {
+        self.fields.lock().unwrap().get(name).cloned()
+    }
+
+    // Returns false if the actor has no field called `name`.
+    pub fn set_field(&self, name: &str, value: Value) -> bool {
+        match self.fields.lock().unwrap().get_mut(name) {
+            Some(field) => {
+                *field = value;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn try_schedule(&self) -> bool {
+        self.scheduled
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
+    }
+
+    fn unschedule(&self) {
+        self.scheduled.store(false, Ordering::SeqCst);
+    }
+}
+
+impl PartialEq for Actor {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl fmt::Debug for Actor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Actor({}#{})", self.class.name.lexeme, self.id)
+    }
+}
+
+// Owns the actors of one program and the worker threads running them. Worker
+// threads are only started once the first message is sent, programs without
+// actors never pay for them.
+pub struct Runtime {
+    pub globals: Arc<Mutex<Environment>>,
+    pub output: Output,
+    scheduler: Scheduler,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+    next_id: AtomicUsize,
+    errors: Mutex<Vec<RuntimeError>>,
+}
+
+impl Runtime {
+    pub fn new(globals: Arc<Mutex<Environment>>, output: Output) -> Arc<Self> {
+        let workers = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        Arc::new(Self {
+            globals,
+            output,
+            scheduler: Scheduler::new(workers),
+            workers: Mutex::new(vec![]),
+            next_id: AtomicUsize::new(1),
+            errors: Mutex::new(vec![]),
+        })
+    }
+
+    pub fn spawn(&self, class: Arc<ActorClass>, fields: HashMap<String, Value>) -> Arc<Actor> {
+        Arc::new(Actor {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst),
+            class,
+            fields: Mutex::new(fields),
+            mailbox: Mailbox::new(),
+            scheduled: AtomicBool::new(false),
+        })
+    }
+
+    pub fn send(self: &Arc<Self>, actor: &Arc<Actor>, message: Message) {
+        self.start();
+        self.scheduler.send(actor, message);
+    }
+
+    fn start(self: &Arc<Self>) {
+        let mut workers = self.workers.lock().unwrap();
+        if !workers.is_empty() || self.scheduler.is_shut_down() {
+            return;
+        }
+        for index in 0..self.scheduler.workers() {
+            let runtime = self.clone();
+            workers.push(thread::spawn(move || {
+                runtime
+                    .scheduler
+                    .run_worker(index, |actor, message| runtime.dispatch(actor, message))
+            }));
+        }
+    }
+
+    // Runs one message on the calling worker thread. Errors can't propagate to
+    // the sender, which has moved on, so they are kept until `wait`.
+    fn dispatch(self: &Arc<Self>, actor: &Arc<Actor>, message: Message) {
+        let mut interpreter = Interpreter::for_actor(self.clone());
+        if let Err(err) = interpreter.run_behaviour(actor, message) {
+            self.errors.lock().unwrap().push(err);
+        }
+    }
+
+    // Blocks until no actor has messages left, returning the errors raised
+    // by behaviours in the meantime.
+    pub fn wait(&self) -> Vec<RuntimeError> {
+        self.scheduler.wait();
+        std::mem::take(&mut *self.errors.lock().unwrap())
+    }
+
+    pub fn shutdown(&self) {
+        self.scheduler.shutdown();
+        let workers: Vec<JoinHandle<()>> = self.workers.lock().unwrap().drain(..).collect();
+        for worker in workers {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use crate::runtime::{Actor, Message};
+
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+// Messages an actor may process before it goes to the back of the queue, so a
+// busy actor can't starve the others scheduled on the same worker.
+const BATCH: usize = 100;
+
+// Idle workers are woken up on every schedule, the timeout only guards
+// against a wake-up racing with a worker going to sleep.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(10);
+
+thread_local! {
+    // `(scheduler address, worker index)` of the worker running on this thread.
+    static WORKER: Cell<Option<(usize, usize)>> = const { Cell::new(None) };
+}
+
+// A work-stealing scheduler. Every worker thread owns a run queue of actors
+// with pending messages; actors scheduled from outside any worker go through
+// the shared injector queue. A worker with nothing to do takes from the
+// injector first, then steals from the back of the other workers' queues.
+//
+// An actor sits in at most one run queue at a time (see `Actor::scheduled`),
+// so it is only ever run by one worker and processes one message at a time.
+pub struct Scheduler {
+    injector: Mutex<VecDeque<Arc<Actor>>>,
+    queues: Vec<Mutex<VecDeque<Arc<Actor>>>>,
+    // Messages sent but not processed yet, zero means the program is quiescent.
+    pending: AtomicUsize,
+    shutdown: AtomicBool,
+    lock: Mutex<()>,
+    wakeup: Condvar,
+    quiescent: Condvar,
+}
+
+impl Scheduler {
+    pub fn new(workers: usize) -> Self {
+        Self {
+            injector: Mutex::new(VecDeque::new()),
+            queues: (0..workers.max(1))
+                .map(|_| Mutex::new(VecDeque::new()))
+                .collect(),
+            pending: AtomicUsize::new(0),
+            shutdown: AtomicBool::new(false),
+            lock: Mutex::new(()),
+            wakeup: Condvar::new(),
+            quiescent: Condvar::new(),
+        }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.queues.len()
+    }
+
+    // Enqueues `message` and makes sure the actor is scheduled. The message is
+    // in the mailbox by the time this returns, which is what gives causal
+    // ordering: anything the sender does afterwards happens after the send.
+    pub fn send(&self, actor: &Arc<Actor>, message: Message) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+        actor.mailbox.push(message);
+        if actor.try_schedule() {
+            self.schedule(actor.clone());
+        }
+    }
+
+    fn schedule(&self, actor: Arc<Actor>) {
+        match self.current_worker() {
+            Some(index) => self.queues[index].lock().unwrap().push_back(actor),
+            None => self.injector.lock().unwrap().push_back(actor),
+        }
+        let _guard = self.lock.lock().unwrap();
+        self.wakeup.notify_one();
+    }
+
+    fn current_worker(&self) -> Option<usize> {
+        let address = self as *const Scheduler as usize;
+        match WORKER.with(|worker| worker.get()) {
+            Some((scheduler, index)) if scheduler == address => Some(index),
+            _ => None,
+        }
+    }
+
+    fn find_work(&self, index: usize) -> Option<Arc<Actor>> {
+        if let Some(actor) = self.queues[index].lock().unwrap().pop_front() {
+            return Some(actor);
+        }
+        if let Some(actor) = self.injector.lock().unwrap().pop_front() {
+            return Some(actor);
+        }
+        let workers = self.queues.len();
+        (1..workers)
+            .map(|offset| (index + offset) % workers)
+            .find_map(|victim| self.queues[victim].lock().unwrap().pop_back())
+    }
+
+    // Runs worker `index` on the calling thread until `shutdown` is called.
+    pub fn run_worker(&self, index: usize, process: impl Fn(&Arc<Actor>, Message)) {
+        let address = self as *const Scheduler as usize;
+        WORKER.with(|worker| worker.set(Some((address, index))));
+
+        while !self.shutdown.load(Ordering::SeqCst) {
+            match self.find_work(index) {
+                Some(actor) => self.run_actor(actor, &process),
+                None => {
+                    let guard = self.lock.lock().unwrap();
+                    let _ = self.wakeup.wait_timeout(guard, IDLE_TIMEOUT).unwrap();
+                }
+            }
+        }
+        WORKER.with(|worker| worker.set(None));
+    }
+
+    fn run_actor(&self, actor: Arc<Actor>, process: &impl Fn(&Arc<Actor>, Message)) {
+        for _ in 0..BATCH {
+            match actor.mailbox.pop() {
+                Some(message) => {
+                    process(&actor, message);
+                    self.message_done();
+                }
+                None => break,
+            }
+        }
+
+        if !actor.mailbox.is_empty() {
+            self.schedule(actor);
+            return;
+        }
+        actor.unschedule();
+        // A message may have arrived between the last pop and `unschedule`,
+        // its sender saw the actor as scheduled and didn't queue it.
+        if !actor.mailbox.is_empty() && actor.try_schedule() {
+            self.schedule(actor);
+        }
+    }
+
+    fn message_done(&self) {
+        if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+            let _guard = self.lock.lock().unwrap();
+            self.quiescent.notify_all();
+        }
+    }
+
+    // Blocks until every message sent so far, and every message those sent in
+    // turn, has been processed.
+    pub fn wait(&self) {
+        let mut guard = self.lock.lock().unwrap();
+        while self.pending.load(Ordering::SeqCst) > 0 {
+            guard = self.quiescent.wait_timeout(guard, IDLE_TIMEOUT).unwrap().0;
+        }
+    }
+
+    pub fn is_shut_down(&self) -> bool {
+        self.shutdown.load(Ordering::SeqCst)
+    }
+
+    pub fn shutdown(&self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        let _guard = self.lock.lock().unwrap();
+        self.wakeup.notify_all();
+    }
+}
@@ -1,7 +1,19 @@
 use std::collections::HashMap;
+use std::fmt;
 
-fn is_alpha(c: char) -> bool {
-    (c >= 'a' && c <= 'z') || (c >= 'A' && c <= 'Z') || c == '_'
+use unicode_xid::UnicodeXID;
+
+// 9223372036854775808 only fits in an Int negated, as the smallest one. It is
+// scanned as `i64::MIN` and the parser reports it anywhere but after `-`.
+const MIN_MAGNITUDE: u64 = 1 << 63;
+
+// Identifiers follow Unicode's XID rules, `_` may start one too.
+fn is_identifier_start(c: char) -> bool {
+    c == '_' || c.is_xid_start()
+}
+
+fn is_identifier_continue(c: char) -> bool {
+    c.is_xid_continue()
 }
 
 pub struct Scanner {
@@ -10,6 +22,14 @@ pub struct Scanner {
     start: usize,
     current: usize,
     line: usize,
+    line_start: usize,
+    start_line: usize,
+    start_column: usize,
+    // One entry per `${` still open: the span of the string part before it,
+    // and how many `{` inside the interpolation are still open.
+    interpolations: Vec<(Span, usize)>,
+    // Whitespace and comments scanned since the last token.
+    trivia: Vec<Trivia>,
     keywords: HashMap<String, TokenType>,
 }
 
@@ -23,7 +43,12 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 1,
-            keywords: keywords,
+            line_start: 0,
+            start_line: 1,
+            start_column: 1,
+            interpolations: Vec::new(),
+            trivia: Vec::new(),
+            keywords,
         }
     }
     fn initialize_keywords() -> HashMap<String, TokenType> {
@@ -31,98 +56,200 @@ impl Scanner {
         keywords.insert("and".to_string(), TokenType::AND);
         keywords.insert("or".to_string(), TokenType::OR);
         keywords.insert("var".to_string(), TokenType::VAR);
+        keywords.insert("true".to_string(), TokenType::True);
+        keywords.insert("false".to_string(), TokenType::False);
+        keywords.insert("nil".to_string(), TokenType::Nil);
+        keywords.insert("let".to_string(), TokenType::LET);
+        keywords.insert("mut".to_string(), TokenType::MUT);
+        keywords.insert("actor".to_string(), TokenType::ACTOR);
+        keywords.insert("be".to_string(), TokenType::BE);
+        keywords.insert("consume".to_string(), TokenType::CONSUME);
+        keywords.insert("fn".to_string(), TokenType::FUN);
+        keywords.insert("return".to_string(), TokenType::RETURN);
+        keywords.insert("if".to_string(), TokenType::IF);
+        keywords.insert("match".to_string(), TokenType::MATCH);
+        keywords.insert("self".to_string(), TokenType::SELF);
+        keywords.insert("else".to_string(), TokenType::ELSE);
+        keywords.insert("while".to_string(), TokenType::WHILE);
+        keywords.insert("for".to_string(), TokenType::FOR);
+        keywords.insert("mod".to_string(), TokenType::MOD);
+        keywords.insert("use".to_string(), TokenType::USE);
         keywords
     }
 
-    pub fn scan_tokens(self: &mut Self) -> Result<Vec<Token>, String> {
+    // Scans the whole source, collecting every error instead of stopping at
+    // the first one.
+    pub fn scan_tokens(&mut self) -> Result<Vec<Token>, Vec<ScanError>> {
+        let (tokens, errors) = self.scan_lossless();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(tokens)
+    }
+
+    // Scans to the end whatever the errors. The text of a lexeme that
+    // couldn't be scanned is kept as skipped trivia, so the tokens still
+    // cover the whole source.
+    pub fn scan_lossless(&mut self) -> (Vec<Token>, Vec<ScanError>) {
         let mut errors = vec![];
 
         while !self.is_at_end() {
             self.start = self.current;
-            match self.scan_token() {
-                Ok(_) => (),
-                Err(msg) => errors.push(msg),
+            self.start_line = self.line;
+            self.start_column = self.column();
+            if let Err(err) = self.scan_token() {
+                self.add_trivia(TriviaKind::Skipped);
+                errors.push(err);
             }
         }
-        self.tokens.push(Token {
-            token_type: TokenType::EOF,
-            lexeme: "".to_string(),
-            literal: None,
-            line_number: self.line,
-        });
+        if let Some((span, _)) = self.interpolations.pop() {
+            errors.push(ScanError {
+                message: "Unterminated string interpolation, expected '}'".to_string(),
+                span,
+            });
+        }
+        let eof = Span::new(self.line, self.column(), self.current, self.current);
+        let mut eof = Token::new(TokenType::EOF, "".to_string(), None, eof);
+        eof.leading_trivia = std::mem::take(&mut self.trivia);
+        self.tokens.push(eof);
 
-        if errors.len() > 0 {
-            return Err("There are some errors in the scanner".to_string());
+        (self.tokens.clone(), errors)
+    }
+
+    // Columns are 1-based, counted in characters from the start of the
+    // current line.
+    fn column(&self) -> usize {
+        self.column_of(self.current)
+    }
+
+    fn column_of(&self, offset: usize) -> usize {
+        self.source[self.line_start..offset].chars().count() + 1
+    }
+
+    // Span of the lexeme scanned so far.
+    fn span(&self) -> Span {
+        Span::new(self.start_line, self.start_column, self.start, self.current)
+    }
+
+    fn error(&self, message: String) -> ScanError {
+        ScanError {
+            message,
+            span: self.span(),
         }
-        Ok(self.tokens.clone())
     }
 
-    fn is_at_end(self: &Self) -> bool {
-        return self.current >= self.source.len();
+    // An error for the part of the current line from `start` on.
+    fn error_at(&self, start: usize, message: String) -> ScanError {
+        let column = self.column_of(start);
+        ScanError {
+            message,
+            span: Span::new(self.line, column, start, self.current),
+        }
     }
 
-    fn scan_token(self: &mut Self) -> Result<(), String> {
+    fn is_at_end(&self) -> bool {
+        self.current >= self.source.len()
+    }
+
+    fn scan_token(&mut self) -> Result<(), ScanError> {
         let c = self.advance();
 
         match c {
-            '(' => Ok(self.add_token(TokenType::LeftParen)),
-            ')' => Ok(self.add_token(TokenType::RightParen)),
+            '(' => self.add_token(TokenType::LeftParen),
+            ')' => self.add_token(TokenType::RightParen),
+            '{' => {
+                if let Some((_, depth)) = self.interpolations.last_mut() {
+                    *depth += 1;
+                }
+                self.add_token(TokenType::LeftBrace)
+            }
+            '}' => match self.interpolations.last_mut() {
+                // The end of `${...}`, the string goes on after it.
+                Some((_, 0)) => {
+                    self.interpolations.pop();
+                    self.string(true)?
+                }
+                Some((_, depth)) => {
+                    *depth -= 1;
+                    self.add_token(TokenType::RightBrace)
+                }
+                None => self.add_token(TokenType::RightBrace),
+            },
+            ';' => self.add_token(TokenType::Semicolon),
+            ':' => self.add_token(TokenType::Colon),
+            '|' => {
+                if self.char_match('>') {
+                    self.add_token(TokenType::Pipe)
+                } else {
+                    self.add_token(TokenType::Bar)
+                }
+            }
+            ',' => self.add_token(TokenType::Comma),
+            '.' => self.add_token(TokenType::Dot),
+            '-' => {
+                if self.char_match('>') {
+                    self.add_token(TokenType::Arrow)
+                } else {
+                    self.add_token(TokenType::Minus)
+                }
+            }
+            '+' => self.add_token(TokenType::Plus),
+            '*' => self.add_token(TokenType::Star),
             '!' => {
-                let token = if self.char_match('=') {
-                    return Ok(self.add_token(TokenType::BangEqual));
+                if self.char_match('=') {
+                    self.add_token(TokenType::BangEqual)
                 } else {
-                    return Ok(self.add_token(TokenType::Bang));
-                };
+                    self.add_token(TokenType::Bang)
+                }
             }
             '=' => {
-                let token = if self.char_match('=') {
-                    return Ok(self.add_token(TokenType::EqualEqual));
+                if self.char_match('=') {
+                    self.add_token(TokenType::EqualEqual)
+                } else if self.char_match('>') {
+                    self.add_token(TokenType::FatArrow)
                 } else {
-                    return Ok(self.add_token(TokenType::Equal));
-                };
+                    self.add_token(TokenType::Equal)
+                }
             }
             '<' => {
-                let token = if self.char_match('=') {
-                    return Ok(self.add_token(TokenType::LessEqual));
+                if self.char_match('=') {
+                    self.add_token(TokenType::LessEqual)
                 } else {
-                    return Ok(self.add_token(TokenType::Less));
-                };
+                    self.add_token(TokenType::Less)
+                }
             }
             '>' => {
-                let token = if self.char_match('=') {
-                    return Ok(self.add_token(TokenType::GreaterEqual));
+                if self.char_match('=') {
+                    self.add_token(TokenType::GreaterEqual)
                 } else {
-                    return Ok(self.add_token(TokenType::Greater));
-                };
+                    self.add_token(TokenType::Greater)
+                }
             }
             '/' => {
                 if self.char_match('/') {
-                    loop {
-                        if self.peek() == '\n' || self.is_at_end() {
-                            break Ok(());
-                        }
-                        self.advance();
-                    }
+                    self.line_comment()
+                } else if self.char_match('*') {
+                    self.block_comment()?
                 } else {
-                    Ok(self.add_token(TokenType::Slash))
+                    self.add_token(TokenType::Slash)
                 }
             }
-            ' ' | '\r' | '\t' => Ok(()),
-            '\n' => Ok(self.line += 1),
-            '"' => Ok(self.string()?),
+            ' ' | '\r' | '\t' | '\n' => self.whitespace(c),
+            '"' => self.string(false)?,
             _ => {
-                if c.is_digit(10) {
-                    Ok(self.number()?)
-                } else if is_alpha(c) {
-                    Ok(self.identifier()?)
+                if c.is_ascii_digit() {
+                    self.number()?
+                } else if is_identifier_start(c) {
+                    self.identifier()?
                 } else {
-                    return Err(format!("Unrecognized char: {}", c));
+                    return Err(self.error(format!("Unrecognized char: {}", c)));
                 }
             }
         }
+        Ok(())
     }
-    fn identifier(self: &mut Self) -> Result<(), String> {
-        while is_alpha(self.peek()) {
+    fn identifier(&mut self) -> Result<(), ScanError> {
+        while is_identifier_continue(self.peek()) {
             self.advance();
         }
 
@@ -143,126 +270,365 @@ impl Scanner {
             }
         }
     }
-    fn number(self: &mut Self) -> Result<(), String> {
-        loop {
-            if self.peek().is_digit(10) {
-                self.advance();
-            } else {
-                break;
+    // A run of whitespace, newlines included.
+    fn whitespace(&mut self, first: char) {
+        if first == '\n' {
+            self.new_line();
+        }
+        while matches!(self.peek(), ' ' | '\r' | '\t' | '\n') {
+            if self.advance() == '\n' {
+                self.new_line();
             }
         }
-        if self.peek() == '.' && self.peek_next().is_digit(10) {
-            //consume the "."
+        self.add_trivia(TriviaKind::Whitespace);
+    }
+    // `///` starts a doc comment, but `////` is an ordinary one.
+    fn line_comment(&mut self) {
+        let doc = self.peek() == '/' && self.peek_next() != '/';
+        while self.peek() != '\n' && !self.is_at_end() {
             self.advance();
-
-            loop {
-                if self.peek().is_digit(10) {
-                    self.advance();
-                } else {
-                    break;
-                }
+        }
+        if doc {
+            self.add_trivia(TriviaKind::DocComment);
+        } else {
+            self.add_trivia(TriviaKind::LineComment);
+        }
+    }
+    // Block comments nest: `/* a /* b */ c */` is one comment.
+    fn block_comment(&mut self) -> Result<(), ScanError> {
+        let opening = Span::new(self.start_line, self.start_column, self.start, self.current);
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                return Err(ScanError {
+                    message: "Unterminated block comment, expected '*/'".to_string(),
+                    span: opening,
+                });
+            }
+            match self.advance() {
+                '/' if self.char_match('*') => depth += 1,
+                '*' if self.char_match('/') => depth -= 1,
+                '\n' => self.new_line(),
+                _ => (),
             }
         }
-        let number_literal: String = self
-            .source
-            .get(self.start..self.current)
-            .unwrap_or("")
-            .to_string();
-
-        let value: f64 = number_literal
-            .parse()
-            .unwrap_or_else(|e| panic!("Error converting to float: {}", e));
-
-        self.add_token_with_literal(TokenType::Number, Some(LiteralValue::FloatValue(value)));
+        self.add_trivia(TriviaKind::BlockComment);
         Ok(())
     }
-    fn peek_next(self: &Self) -> char {
-        if self.current + 1 >= self.source.len() {
-            return '\0';
+    fn add_trivia(&mut self, kind: TriviaKind) {
+        let trivia = Trivia {
+            kind,
+            text: self.lexeme(),
+            span: self.span(),
         };
-        let c = self.source.as_bytes()[self.current + 1] as char;
-        return c;
+        self.trivia.push(trivia);
     }
-    fn string(self: &mut Self) -> Result<(), String> {
-        loop {
-            if self.peek() != '"' && !self.is_at_end() {
-                if self.peek() == '\n' {
-                    self.line += 1
-                };
+    // Number literals:
+    //
+    //   42  1_000_000          Int
+    //   0xff  0b1010  0o17     Int in base 16, 2 or 8
+    //   4.2  1.5e-3  2e10      Float, a fraction or an exponent makes one
+    //
+    // `_` may separate digits anywhere but at the end of a run of digits.
+    fn number(&mut self) -> Result<(), ScanError> {
+        let first = self.source[self.start..].chars().next().unwrap_or('\0');
+        if first == '0' {
+            let radix = match self.peek() {
+                'x' | 'X' => Some((16, "hexadecimal")),
+                'b' | 'B' => Some((2, "binary")),
+                'o' | 'O' => Some((8, "octal")),
+                _ => None,
+            };
+            if let Some((radix, name)) = radix {
                 self.advance();
-            } else {
-                break;
+                return self.radix_number(radix, name);
             }
         }
 
-        if self.is_at_end() {
-            return Err("Undetermined string.".to_string());
+        self.digits(10);
+        let mut float = false;
+        if self.peek() == '.' && self.peek_next().is_ascii_digit() {
+            float = true;
+            self.advance();
+            self.digits(10);
         }
+        if matches!(self.peek(), 'e' | 'E') {
+            float = true;
+            self.advance();
+            if matches!(self.peek(), '+' | '-') {
+                self.advance();
+            }
+            if !self.peek().is_ascii_digit() {
+                self.skip_alphanumeric();
+                return Err(self.error(format!(
+                    "Expected digits in the exponent of {}",
+                    self.lexeme()
+                )));
+            }
+            self.digits(10);
+        }
+        self.check_number_end(10, "number")?;
 
-        self.advance();
+        let lexeme = self.lexeme();
+        let digits = lexeme.replace('_', "");
+        let literal = if float {
+            match digits.parse::<f64>() {
+                Ok(value) if value.is_finite() => LiteralValue::FloatValue(value),
+                _ => return Err(self.error(format!("Float literal {} is out of range", lexeme))),
+            }
+        } else {
+            match digits.parse() {
+                Ok(value) => LiteralValue::IntValue(value),
+                Err(_) if digits.parse() == Ok(MIN_MAGNITUDE) => LiteralValue::IntValue(i64::MIN),
+                Err(_) => {
+                    return Err(
+                        self.error(format!("Integer literal {} does not fit in an Int", lexeme))
+                    )
+                }
+            }
+        };
 
-        let string_literal: String = self
-            .source
-            .get(self.start + 1..self.current - 1)
+        self.add_token_with_literal(TokenType::Number, Some(literal));
+        Ok(())
+    }
+    // Called with the `0x`, `0b` or `0o` prefix already consumed.
+    fn radix_number(&mut self, radix: u32, name: &str) -> Result<(), ScanError> {
+        self.digits(radix);
+        self.check_number_end(radix, name)?;
+
+        let lexeme = self.lexeme();
+        let digits = lexeme[2..].replace('_', "");
+        if digits.is_empty() {
+            return Err(self.error(format!("Expected {} digits after '{}'", name, &lexeme[..2])));
+        }
+        let value = match i64::from_str_radix(&digits, radix) {
+            Ok(value) => value,
+            Err(_) if u64::from_str_radix(&digits, radix) == Ok(MIN_MAGNITUDE) => i64::MIN,
+            Err(_) => {
+                return Err(self.error(format!("Integer literal {} does not fit in an Int", lexeme)))
+            }
+        };
+        self.add_token_with_literal(TokenType::Number, Some(LiteralValue::IntValue(value)));
+        Ok(())
+    }
+    fn digits(&mut self, radix: u32) {
+        while self.peek().is_digit(radix) || self.peek() == '_' {
+            self.advance();
+        }
+    }
+    // A literal runs up to the first character that can't continue an
+    // identifier, `12ab` is one malformed literal rather than `12` and `ab`.
+    fn check_number_end(&mut self, radix: u32, name: &str) -> Result<(), ScanError> {
+        let c = self.peek();
+        if c.is_alphanumeric() {
+            self.skip_alphanumeric();
+            return Err(self.error(format!(
+                "Invalid digit '{}' in {} literal {}",
+                c,
+                name,
+                self.lexeme()
+            )));
+        }
+        let lexeme: Vec<char> = self.lexeme().chars().collect();
+        let is_digit = |c: Option<&char>| c.is_some_and(|c| c.is_digit(radix));
+        for (i, c) in lexeme.iter().enumerate() {
+            if *c == '_' && (i == 0 || !is_digit(lexeme.get(i - 1)) || !is_digit(lexeme.get(i + 1)))
+            {
+                return Err(self.error(format!(
+                    "'_' must separate digits in {} literal {}",
+                    name,
+                    self.lexeme()
+                )));
+            }
+        }
+        Ok(())
+    }
+    fn skip_alphanumeric(&mut self) {
+        while self.peek().is_alphanumeric() || self.peek() == '_' {
+            self.advance();
+        }
+    }
+    fn lexeme(&self) -> String {
+        self.source
+            .get(self.start..self.current)
             .unwrap_or("")
-            .to_string();
+            .to_string()
+    }
+    fn peek_next(&self) -> char {
+        self.source[self.current..].chars().nth(1).unwrap_or('\0')
+    }
+    // Scans the rest of a string, after its opening '"' or, when `resumed`,
+    // after the '}' closing an interpolation. Each `${` ends a part, the
+    // expression tokens come next and the string resumes at the matching '}':
+    //
+    //   "Age: ${age} years"   Interpolation("Age: ") Identifier(age) InterpolationEnd(" years")
+    //
+    // An invalid escape is reported once the string is scanned, so the rest
+    // of it isn't mistaken for code.
+    fn string(&mut self, resumed: bool) -> Result<(), ScanError> {
+        let mut value = String::new();
+        let mut segment_start = self.current;
+        let mut error = None;
 
-        self.add_token_with_literal(
-            TokenType::String,
-            Some(LiteralValue::StringValue(string_literal)),
-        );
+        loop {
+            if self.is_at_end() {
+                return Err(self.error("Unterminated string.".to_string()));
+            }
+            match self.peek() {
+                '"' => break,
+                '$' if self.peek_next() == '{' => {
+                    value.push_str(&self.source[segment_start..self.current]);
+                    self.advance();
+                    self.advance();
+                    self.interpolations.push((self.span(), 0));
+                    let token_type = if resumed {
+                        TokenType::InterpolationMiddle
+                    } else {
+                        TokenType::Interpolation
+                    };
+                    self.add_token_with_literal(token_type, Some(LiteralValue::StringValue(value)));
+                    return Ok(());
+                }
+                '\\' => {
+                    value.push_str(&self.source[segment_start..self.current]);
+                    match self.escape() {
+                        Ok(c) => value.push(c),
+                        Err(err) => {
+                            error.get_or_insert(err);
+                        }
+                    }
+                    segment_start = self.current;
+                }
+                '\n' => {
+                    self.advance();
+                    self.new_line();
+                }
+                _ => {
+                    self.advance();
+                }
+            }
+        }
+        value.push_str(&self.source[segment_start..self.current]);
+        self.advance();
 
+        if let Some(err) = error {
+            return Err(err);
+        }
+        let token_type = if resumed {
+            TokenType::InterpolationEnd
+        } else {
+            TokenType::String
+        };
+        self.add_token_with_literal(token_type, Some(LiteralValue::StringValue(value)));
         Ok(())
     }
-
-    fn peek(self: &Self) -> char {
+    // `\n`, `\t`, `\r`, `\0`, `\"`, `\\`, `\$` and `\u{1F600}`, with the
+    // backslash not consumed yet.
+    fn escape(&mut self) -> Result<char, ScanError> {
+        let start = self.current;
+        self.advance();
         if self.is_at_end() {
-            return '\0';
+            return Err(self.error_at(start, "Unterminated string.".to_string()));
+        }
+        let c = self.advance();
+        let escaped = match c {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            '0' => '\0',
+            '"' => '"',
+            '\\' => '\\',
+            '$' => '$',
+            'u' => return self.unicode_escape(start),
+            _ => {
+                let err = self.error_at(
+                    start,
+                    format!("Invalid escape sequence '\\{}'", c.escape_default()),
+                );
+                if c == '\n' {
+                    self.new_line();
+                }
+                return Err(err);
+            }
         };
-        return self.source.as_bytes()[self.current] as char;
+        Ok(escaped)
+    }
+    fn unicode_escape(&mut self, start: usize) -> Result<char, ScanError> {
+        if !self.char_match('{') {
+            return Err(self.error_at(start, "Expected '{' after '\\u'".to_string()));
+        }
+        let digits_start = self.current;
+        while self.peek().is_ascii_hexdigit() {
+            self.advance();
+        }
+        let digits = self.source[digits_start..self.current].to_string();
+        if !self.char_match('}') {
+            return Err(self.error_at(
+                start,
+                "Expected '}' after the digits of a unicode escape".to_string(),
+            ));
+        }
+        if digits.is_empty() || digits.len() > 6 {
+            return Err(self.error_at(
+                start,
+                "A unicode escape takes 1 to 6 hexadecimal digits".to_string(),
+            ));
+        }
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| {
+                self.error_at(
+                    start,
+                    format!(
+                        "Invalid unicode escape '\\u{{{}}}', not a character",
+                        digits
+                    ),
+                )
+            })
     }
 
-    fn char_match(self: &mut Self, char_to_check: char) -> bool {
-        if self.is_at_end() {
-            return false;
-        };
-        let c = self.source.as_bytes()[self.current] as char;
-        if c != char_to_check {
-            return false;
+    // `current` is a byte offset, always at the start of a character.
+    fn peek(&self) -> char {
+        self.source[self.current..].chars().next().unwrap_or('\0')
+    }
+
+    fn char_match(&mut self, char_to_check: char) -> bool {
+        if self.is_at_end() || self.peek() != char_to_check {
+            false
         } else {
-            self.current += 1;
-            return true;
+            self.advance();
+            true
         }
     }
-    fn advance(self: &mut Self) -> char {
-        let c = self.source.as_bytes()[self.current];
-        self.current += 1;
+    // Called once the '\n' has been consumed.
+    fn new_line(&mut self) {
+        self.line += 1;
+        self.line_start = self.current;
+    }
 
-        c as char
+    fn advance(&mut self) -> char {
+        let c = self.peek();
+        self.current += c.len_utf8();
+        c
     }
-    fn add_token(self: &mut Self, token_type: TokenType) {
+    fn add_token(&mut self, token_type: TokenType) {
         self.add_token_with_literal(token_type, None);
     }
 
-    fn add_token_with_literal(
-        self: &mut Self,
-        token_type: TokenType,
-        literal: Option<LiteralValue>,
-    ) {
+    fn add_token_with_literal(&mut self, token_type: TokenType, literal: Option<LiteralValue>) {
         let text: String = self
             .source
             .get(self.start..self.current)
             .unwrap_or("")
             .to_string();
 
-        self.tokens.push(Token {
-            token_type: token_type,
-            lexeme: text,
-            literal: literal,
-            line_number: self.line,
-        })
+        let mut token = Token::new(token_type, text, literal, self.span());
+        token.leading_trivia = std::mem::take(&mut self.trivia);
+        self.tokens.push(token)
     }
 }
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     LeftParen,
@@ -274,56 +640,125 @@ pub enum TokenType {
     Minus,
     Plus,
     Semicolon,
+    Colon,
+    Bar,
     Slash,
     Star,
 
     // One or two character tokens.
+    Arrow,
     Bang,
     BangEqual,
     Equal,
     EqualEqual,
+    FatArrow,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
+    Pipe,
 
     // Literals.
     Identifier,
     String,
+    // The parts of an interpolated string: `"...${`, `}...${` and `}..."`.
+    Interpolation,
+    InterpolationMiddle,
+    InterpolationEnd,
     Number,
 
     // Keywords.
+    ACTOR,
     AND,
-    CLASS,
+    BE,
+    CONSUME,
     ELSE,
     False,
     FUN,
     FOR,
     IF,
+    LET,
+    MATCH,
+    MOD,
+    MUT,
     Nil,
     OR,
-    PRINT,
     RETURN,
-    SUPER,
-    THIS,
+    SELF,
     True,
+    USE,
     VAR,
     WHILE,
 
     EOF,
 }
 
-impl std::fmt::Display for TokenType {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Display for TokenType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "{:?}", self)
     }
 }
+#[allow(clippy::enum_variant_names)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum LiteralValue {
     IntValue(i64),
     FloatValue(f64),
     StringValue(String),
-    IdentifierValue(String),
+}
+
+// A location in the source: 1-based line and column of the first character,
+// the column counted in characters, plus the half-open byte range
+// `start..end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(line: usize, column: usize, start: usize, end: usize) -> Self {
+        Self {
+            line,
+            column,
+            start,
+            end,
+        }
+    }
+
+    // The smallest span covering both `self` and `other`.
+    pub fn to(&self, other: Span) -> Span {
+        let first = if self.start <= other.start {
+            self
+        } else {
+            &other
+        };
+        Span {
+            line: first.line,
+            column: first.column,
+            start: first.start,
+            end: self.end.max(other.end),
+        }
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanError {
+    pub message: String,
+    pub span: Span,
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[line {}] Error: {}", self.span, self.message)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -331,7 +766,40 @@ pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub literal: Option<LiteralValue>,
-    pub line_number: usize,
+    pub span: Span,
+    // The whitespace and comments between the previous token and this one,
+    // the trivia at the end of the source goes to EOF.
+    pub leading_trivia: Vec<Trivia>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriviaKind {
+    Whitespace,
+    LineComment,
+    BlockComment,
+    DocComment,
+    // Source text that couldn't be scanned, next to a ScanError.
+    Skipped,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trivia {
+    pub kind: TriviaKind,
+    pub text: String,
+    pub span: Span,
+}
+
+impl Trivia {
+    // The text of a `///` comment, without the slashes and the space after.
+    pub fn doc_text(&self) -> Option<&str> {
+        match self.kind {
+            TriviaKind::DocComment => {
+                let text = self.text.strip_prefix("///").unwrap_or(&self.text);
+                Some(text.strip_prefix(' ').unwrap_or(text))
+            }
+            _ => None,
+        }
+    }
 }
 
 impl Token {
@@ -339,18 +807,35 @@ impl Token {
         token_type: TokenType,
         lexeme: String,
         literal: Option<LiteralValue>,
-        line_number: usize,
+        span: Span,
     ) -> Self {
         Self {
             token_type,
             lexeme,
             literal,
-            line_number,
+            span,
+            leading_trivia: vec![],
+        }
+    }
+
+    // The `///` comments right before the token, one line each.
+    pub fn doc_comment(&self) -> Option<String> {
+        let lines: Vec<&str> = self
+            .leading_trivia
+            .iter()
+            .filter_map(Trivia::doc_text)
+            .collect();
+        if lines.is_empty() {
+            None
+        } else {
+            Some(lines.join("\n"))
         }
     }
+}
 
-    pub fn to_string(self: &Self) -> String {
-        format!("{} {} {:?}", self.token_type, self.lexeme, self.literal)
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} {:?}", self.token_type, self.lexeme, self.literal)
     }
 }
 
@@ -360,33 +845,39 @@ mod tests {
 
     #[test]
     fn handle_one_char_tokens() {
-        let source = "(( ))";
+        let source = "(( ))|";
         let mut scanner = Scanner::new(source);
-        scanner.scan_tokens();
+        scanner.scan_tokens().unwrap();
 
-        assert_eq!(scanner.tokens.len(), 5);
+        assert_eq!(scanner.tokens.len(), 6);
         assert_eq!(scanner.tokens[0].token_type, TokenType::LeftParen);
         assert_eq!(scanner.tokens[2].token_type, TokenType::RightParen);
+        assert_eq!(scanner.tokens[4].token_type, TokenType::Bar);
 
-        assert_eq!(scanner.tokens[4].token_type, TokenType::EOF);
+        assert_eq!(scanner.tokens[5].token_type, TokenType::EOF);
     }
 
     #[test]
     fn handle_two_char_tokens() {
-        let source = "! != == >=";
+        let source = "! != == >= -> - |> | =>";
         let mut scanner = Scanner::new(source);
-        scanner.scan_tokens();
+        scanner.scan_tokens().unwrap();
 
         assert_eq!(scanner.tokens[0].token_type, TokenType::Bang);
 
         assert_eq!(scanner.tokens[1].token_type, TokenType::BangEqual);
+        assert_eq!(scanner.tokens[4].token_type, TokenType::Arrow);
+        assert_eq!(scanner.tokens[5].token_type, TokenType::Minus);
+        assert_eq!(scanner.tokens[6].token_type, TokenType::Pipe);
+        assert_eq!(scanner.tokens[7].token_type, TokenType::Bar);
+        assert_eq!(scanner.tokens[8].token_type, TokenType::FatArrow);
     }
 
     #[test]
     fn handle_string_literal() {
         let source = "\"ABC\" !";
         let mut scanner = Scanner::new(source);
-        scanner.scan_tokens();
+        scanner.scan_tokens().unwrap();
 
         assert_eq!(scanner.tokens[0].token_type, TokenType::String);
         assert_eq!(scanner.tokens[1].token_type, TokenType::Bang);
@@ -412,7 +903,7 @@ mod tests {
     fn handle_string_literal_multiline() {
         let source = "\"ABC\nabc\"";
         let mut scanner = Scanner::new(source);
-        scanner.scan_tokens();
+        scanner.scan_tokens().unwrap();
 
         assert_eq!(scanner.tokens[0].token_type, TokenType::String);
         assert_eq!(scanner.tokens[1].token_type, TokenType::EOF);
@@ -422,6 +913,117 @@ mod tests {
         }
     }
     #[test]
+    fn string_escapes() {
+        let source = r#""a\tb\n\"q\" \\ \$ \u{41}\u{1F600}""#;
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            scanner.tokens[0].literal,
+            Some(LiteralValue::StringValue(
+                "a\tb\n\"q\" \\ $ A😀".to_string()
+            ))
+        );
+        assert_eq!(scanner.tokens[0].lexeme, source);
+    }
+    #[test]
+    fn invalid_escapes_are_errors() {
+        let message = |source: &str| Scanner::new(source).scan_tokens().unwrap_err()[0].clone();
+
+        let error = message(r#"let s = "a\qb"; 1 +"#);
+        assert_eq!(error.message, "Invalid escape sequence '\\q'");
+        assert_eq!((error.span.start, error.span.end), (10, 12));
+
+        let cases = [
+            (r#""\u41""#, "Expected '{' after '\\u'"),
+            (
+                r#""\u{}""#,
+                "A unicode escape takes 1 to 6 hexadecimal digits",
+            ),
+            (
+                r#""\u{41""#,
+                "Expected '}' after the digits of a unicode escape",
+            ),
+            (
+                r#""\u{D800}""#,
+                "Invalid unicode escape '\\u{D800}', not a character",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(message(source).message, expected, "{}", source);
+        }
+    }
+    #[test]
+    fn interpolated_strings() {
+        let source = r#""Age: ${self.age} ${ {a: 1}.a }!" x"#;
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let types: Vec<TokenType> = scanner
+            .tokens
+            .iter()
+            .map(|t| t.token_type.clone())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Interpolation,
+                TokenType::SELF,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::InterpolationMiddle,
+                TokenType::LeftBrace,
+                TokenType::Identifier,
+                TokenType::Colon,
+                TokenType::Number,
+                TokenType::RightBrace,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::InterpolationEnd,
+                TokenType::Identifier,
+                TokenType::EOF,
+            ]
+        );
+        assert_eq!(
+            scanner.tokens[0].literal,
+            Some(LiteralValue::StringValue("Age: ".to_string()))
+        );
+        assert_eq!(scanner.tokens[4].lexeme, "} ${");
+        assert_eq!(scanner.tokens[12].lexeme, "}!\"");
+
+        let errors = Scanner::new(r#""a ${b"#).scan_tokens().unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Unterminated string interpolation, expected '}'"
+        );
+    }
+    #[test]
+    fn unicode_identifiers_and_strings() {
+        let source = "let größe_2 = \"日本語\";\nπ € x";
+        let (tokens, errors) = {
+            let mut scanner = Scanner::new(source);
+            let errors = scanner.scan_tokens().unwrap_err();
+            (scanner.tokens, errors)
+        };
+
+        assert_eq!(tokens[1].token_type, TokenType::Identifier);
+        assert_eq!(tokens[1].lexeme, "größe_2");
+        assert_eq!(tokens[1].span, Span::new(1, 5, 4, 13));
+        assert_eq!(
+            tokens[3].literal,
+            Some(LiteralValue::StringValue("日本語".to_string()))
+        );
+        assert_eq!(tokens[3].span, Span::new(1, 15, 16, 27));
+        assert_eq!(tokens[4].span, Span::new(1, 20, 27, 28));
+
+        // Columns count characters, the byte range covers the whole character.
+        assert_eq!(tokens[5].lexeme, "π");
+        assert_eq!(tokens[5].span, Span::new(2, 1, 29, 31));
+        assert_eq!(errors[0].message, "Unrecognized char: €");
+        assert_eq!(errors[0].span, Span::new(2, 3, 32, 35));
+        assert_eq!(tokens[6].span, Span::new(2, 5, 36, 37));
+    }
+    #[test]
     fn handle_number_literal_multiline() {
         let source = "12.3\n33.33\n1444.12";
         let mut scanner = Scanner::new(source);
@@ -443,6 +1045,119 @@ mod tests {
         }
     }
     #[test]
+    fn integer_literals_are_ints() {
+        let source = "42 0 9223372036854775807 4.0";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens[0].literal, Some(LiteralValue::IntValue(42)));
+        assert_eq!(scanner.tokens[1].literal, Some(LiteralValue::IntValue(0)));
+        assert_eq!(
+            scanner.tokens[2].literal,
+            Some(LiteralValue::IntValue(i64::MAX))
+        );
+        assert_eq!(
+            scanner.tokens[3].literal,
+            Some(LiteralValue::FloatValue(4.0))
+        );
+
+        let tokens = Scanner::new("9223372036854775808 0x8000_0000_0000_0000")
+            .scan_tokens()
+            .unwrap();
+        assert_eq!(tokens[0].literal, Some(LiteralValue::IntValue(i64::MIN)));
+        assert_eq!(tokens[1].literal, Some(LiteralValue::IntValue(i64::MIN)));
+
+        let errors = Scanner::new("9223372036854775809")
+            .scan_tokens()
+            .unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Integer literal 9223372036854775809 does not fit in an Int"
+        );
+    }
+    #[test]
+    fn radix_literals() {
+        let source = "0xff 0XFF 0b1010 0o17 0x7fff_ffff_ffff_ffff";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let literals: Vec<_> = scanner.tokens[..5]
+            .iter()
+            .map(|token| token.literal.clone())
+            .collect();
+        assert_eq!(
+            literals,
+            vec![
+                Some(LiteralValue::IntValue(255)),
+                Some(LiteralValue::IntValue(255)),
+                Some(LiteralValue::IntValue(10)),
+                Some(LiteralValue::IntValue(15)),
+                Some(LiteralValue::IntValue(i64::MAX)),
+            ]
+        );
+        assert_eq!(scanner.tokens[0].lexeme, "0xff");
+    }
+    #[test]
+    fn separators_and_exponents() {
+        let source = "1_000_000 1_000.000_5 1.5e-3 2e10 2E+2";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(
+            scanner.tokens[0].literal,
+            Some(LiteralValue::IntValue(1_000_000))
+        );
+        assert_eq!(scanner.tokens[0].lexeme, "1_000_000");
+        assert_eq!(
+            scanner.tokens[1].literal,
+            Some(LiteralValue::FloatValue(1000.0005))
+        );
+        assert_eq!(
+            scanner.tokens[2].literal,
+            Some(LiteralValue::FloatValue(0.0015))
+        );
+        assert_eq!(
+            scanner.tokens[3].literal,
+            Some(LiteralValue::FloatValue(2e10))
+        );
+        assert_eq!(
+            scanner.tokens[4].literal,
+            Some(LiteralValue::FloatValue(200.0))
+        );
+        assert_eq!(scanner.tokens[5].token_type, TokenType::EOF);
+    }
+    #[test]
+    fn malformed_number_literals() {
+        let message = |source: &str| Scanner::new(source).scan_tokens().unwrap_err()[0].clone();
+
+        let cases = [
+            ("0x", "Expected hexadecimal digits after '0x'"),
+            ("0b102", "Invalid digit '2' in binary literal 0b102"),
+            ("0o8", "Invalid digit '8' in octal literal 0o8"),
+            ("12ab", "Invalid digit 'a' in number literal 12ab"),
+            ("1__0", "'_' must separate digits in number literal 1__0"),
+            ("1_", "'_' must separate digits in number literal 1_"),
+            (
+                "0x_1",
+                "'_' must separate digits in hexadecimal literal 0x_1",
+            ),
+            ("1_.5", "'_' must separate digits in number literal 1_.5"),
+            ("1e", "Expected digits in the exponent of 1e"),
+            ("1e+x", "Expected digits in the exponent of 1e+x"),
+            ("1e400", "Float literal 1e400 is out of range"),
+            (
+                "0x1_0000_0000_0000_0000",
+                "Integer literal 0x1_0000_0000_0000_0000 does not fit in an Int",
+            ),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(message(source).message, expected, "{}", source);
+        }
+        // The span covers the whole malformed literal.
+        let error = message("let x = 0b102;");
+        assert_eq!((error.span.start, error.span.end), (8, 13));
+    }
+    #[test]
     fn handle_identifiers() {
         let source = "var verm_at = 23.3";
         let mut scanner = Scanner::new(source);
@@ -455,11 +1170,258 @@ mod tests {
     }
     #[test]
     fn handle_reserved_keywords() {
-        let source = "and or";
+        let source = "and or return if match consume";
         let mut scanner = Scanner::new(source);
         scanner.scan_tokens().unwrap();
 
         assert_eq!(scanner.tokens[0].token_type, TokenType::AND);
         assert_eq!(scanner.tokens[1].token_type, TokenType::OR);
+        assert_eq!(scanner.tokens[2].token_type, TokenType::RETURN);
+        assert_eq!(scanner.tokens[3].token_type, TokenType::IF);
+        assert_eq!(scanner.tokens[4].token_type, TokenType::MATCH);
+        assert_eq!(scanner.tokens[5].token_type, TokenType::CONSUME);
+    }
+    // A source for every token type, and which of the scanned tokens has it.
+    // The match is exhaustive, so a new token type needs a case here.
+    fn sample(token_type: &TokenType) -> (&'static str, usize) {
+        use TokenType::*;
+        match token_type {
+            LeftParen => ("(", 0),
+            RightParen => (")", 0),
+            LeftBrace => ("{", 0),
+            RightBrace => ("}", 0),
+            Comma => (",", 0),
+            Dot => (".", 0),
+            Minus => ("-", 0),
+            Plus => ("+", 0),
+            Semicolon => (";", 0),
+            Colon => (":", 0),
+            Bar => ("|", 0),
+            Slash => ("/", 0),
+            Star => ("*", 0),
+            Arrow => ("->", 0),
+            Bang => ("!", 0),
+            BangEqual => ("!=", 0),
+            Equal => ("=", 0),
+            EqualEqual => ("==", 0),
+            FatArrow => ("=>", 0),
+            Greater => (">", 0),
+            GreaterEqual => (">=", 0),
+            Less => ("<", 0),
+            LessEqual => ("<=", 0),
+            Pipe => ("|>", 0),
+            Identifier => ("name", 0),
+            String => ("\"text\"", 0),
+            Interpolation => ("\"a${b}\"", 0),
+            InterpolationMiddle => ("\"${a}b${c}\"", 2),
+            InterpolationEnd => ("\"${a}b\"", 2),
+            Number => ("1", 0),
+            ACTOR => ("actor", 0),
+            AND => ("and", 0),
+            BE => ("be", 0),
+            CONSUME => ("consume", 0),
+            ELSE => ("else", 0),
+            False => ("false", 0),
+            FUN => ("fn", 0),
+            FOR => ("for", 0),
+            IF => ("if", 0),
+            LET => ("let", 0),
+            MATCH => ("match", 0),
+            MOD => ("mod", 0),
+            MUT => ("mut", 0),
+            Nil => ("nil", 0),
+            OR => ("or", 0),
+            RETURN => ("return", 0),
+            SELF => ("self", 0),
+            True => ("true", 0),
+            USE => ("use", 0),
+            VAR => ("var", 0),
+            WHILE => ("while", 0),
+            EOF => ("", 0),
+        }
+    }
+    #[test]
+    fn every_token_type_is_scanned() {
+        use TokenType::*;
+        let token_types = [
+            LeftParen,
+            RightParen,
+            LeftBrace,
+            RightBrace,
+            Comma,
+            Dot,
+            Minus,
+            Plus,
+            Semicolon,
+            Colon,
+            Bar,
+            Slash,
+            Star,
+            Arrow,
+            Bang,
+            BangEqual,
+            Equal,
+            EqualEqual,
+            FatArrow,
+            Greater,
+            GreaterEqual,
+            Less,
+            LessEqual,
+            Pipe,
+            Identifier,
+            String,
+            Interpolation,
+            InterpolationMiddle,
+            InterpolationEnd,
+            Number,
+            ACTOR,
+            AND,
+            BE,
+            CONSUME,
+            ELSE,
+            False,
+            FUN,
+            FOR,
+            IF,
+            LET,
+            MATCH,
+            MOD,
+            MUT,
+            Nil,
+            OR,
+            RETURN,
+            SELF,
+            True,
+            USE,
+            VAR,
+            WHILE,
+            EOF,
+        ];
+        for token_type in token_types {
+            let (source, index) = sample(&token_type);
+            let tokens = Scanner::new(source).scan_tokens().unwrap();
+            assert_eq!(tokens[index].token_type, token_type, "{:?}", source);
+            assert_eq!(tokens.last().unwrap().token_type, EOF);
+        }
+    }
+    #[test]
+    fn keywords_need_the_whole_word() {
+        let source = "module user elsewhere fnord lets";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+        assert!(tokens[..5]
+            .iter()
+            .all(|token| token.token_type == TokenType::Identifier));
+    }
+    #[test]
+    fn comments_are_trivia() {
+        let source = "a /* x /* y */\n z */ b // c\n/// d\n//// e\nf";
+        let tokens = Scanner::new(source).scan_tokens().unwrap();
+
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.token_type.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::Identifier,
+                TokenType::Identifier,
+                TokenType::Identifier,
+                TokenType::EOF
+            ]
+        );
+        assert_eq!(tokens[1].span, Span::new(2, 7, 21, 22));
+        assert_eq!(tokens[1].leading_trivia[1].text, "/* x /* y */\n z */");
+        assert_eq!(tokens[1].leading_trivia[1].kind, TriviaKind::BlockComment);
+
+        let kinds: Vec<TriviaKind> = tokens[2].leading_trivia.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TriviaKind::Whitespace,
+                TriviaKind::LineComment,
+                TriviaKind::Whitespace,
+                TriviaKind::DocComment,
+                TriviaKind::Whitespace,
+                TriviaKind::LineComment,
+                TriviaKind::Whitespace,
+            ]
+        );
+        assert_eq!(tokens[2].doc_comment().as_deref(), Some("d"));
+
+        let errors = Scanner::new("a /* /* */").scan_tokens().unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "Unterminated block comment, expected '*/'"
+        );
+        assert_eq!(errors[0].span, Span::new(1, 3, 2, 4));
+    }
+    #[test]
+    fn handle_spans() {
+        let source = "let a = 1;\n  \"x\ny\" b";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens[0].span, Span::new(1, 1, 0, 3));
+        assert_eq!(scanner.tokens[3].span, Span::new(1, 9, 8, 9));
+        assert_eq!(scanner.tokens[5].span, Span::new(2, 3, 13, 18));
+        assert_eq!(scanner.tokens[6].span, Span::new(3, 4, 19, 20));
+        assert_eq!(scanner.tokens[7].span, Span::new(3, 5, 20, 20));
+    }
+    #[test]
+    fn scan_error_points_at_offending_char() {
+        let mut scanner = Scanner::new("1 +\n  @");
+        let errors = scanner.scan_tokens().unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, Span::new(2, 3, 6, 7));
+        assert_eq!(
+            errors[0].to_string(),
+            "[line 2:3] Error: Unrecognized char: @"
+        );
+    }
+    #[test]
+    fn handle_actor_keywords() {
+        let source = "actor Main { be go(by: Int) { self.age } fn }";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        let types: Vec<TokenType> = scanner
+            .tokens
+            .iter()
+            .map(|t| t.token_type.clone())
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                TokenType::ACTOR,
+                TokenType::Identifier,
+                TokenType::LeftBrace,
+                TokenType::BE,
+                TokenType::Identifier,
+                TokenType::LeftParen,
+                TokenType::Identifier,
+                TokenType::Colon,
+                TokenType::Identifier,
+                TokenType::RightParen,
+                TokenType::LeftBrace,
+                TokenType::SELF,
+                TokenType::Dot,
+                TokenType::Identifier,
+                TokenType::RightBrace,
+                TokenType::FUN,
+                TokenType::RightBrace,
+                TokenType::EOF,
+            ]
+        );
+    }
+    #[test]
+    fn handle_let_mut_block() {
+        let source = "{ let mut age = 23; }";
+        let mut scanner = Scanner::new(source);
+        scanner.scan_tokens().unwrap();
+
+        assert_eq!(scanner.tokens[0].token_type, TokenType::LeftBrace);
+        assert_eq!(scanner.tokens[1].token_type, TokenType::LET);
+        assert_eq!(scanner.tokens[2].token_type, TokenType::MUT);
+        assert_eq!(scanner.tokens[3].token_type, TokenType::Identifier);
+        assert_eq!(scanner.tokens[6].token_type, TokenType::Semicolon);
+        assert_eq!(scanner.tokens[7].token_type, TokenType::RightBrace);
     }
 }
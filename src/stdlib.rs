@@ -0,0 +1,121 @@
+use crate::interpreter::{Interpreter, NativeFunction, Value};
+
+use std::fmt;
+use std::sync::Arc;
+
+// A namespace of native functions, called with a path: `String.trim_string(s)`.
+pub struct Module {
+    pub name: &'static str,
+    pub functions: Vec<NativeFunction>,
+}
+
+impl Module {
+    pub fn function(&self, name: &str) -> Option<&NativeFunction> {
+        self.functions.iter().find(|function| function.name == name)
+    }
+}
+
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl fmt::Debug for Module {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Module({})", self.name)
+    }
+}
+
+// Everything defined in the global scope before a program starts.
+pub fn globals() -> Vec<(&'static str, Value)> {
+    vec![
+        (
+            "print",
+            Value::NativeFunction(NativeFunction {
+                name: "print",
+                params: &["_"],
+                returns: "Nil",
+                function: print,
+            }),
+        ),
+        ("String", Value::Module(Arc::new(string_module()))),
+    ]
+}
+
+fn print(interpreter: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let output = interpreter.output();
+    let mut output = output.lock().unwrap();
+    writeln!(output, "{}", arguments[0])
+        .map_err(|err| err.to_string())
+        .map(|_| Value::Nil)
+}
+
+fn string_module() -> Module {
+    let function = |name, returns, function| NativeFunction {
+        name,
+        params: &["String"],
+        returns,
+        function,
+    };
+    Module {
+        name: "String",
+        functions: vec![
+            function("trim_string", "String", trim_string),
+            function("parse_to_int", "Int", parse_to_int),
+            function("length", "Int", length),
+            function("to_upper", "String", to_upper),
+            function("to_lower", "String", to_lower),
+            NativeFunction {
+                name: "from",
+                params: &["_"],
+                returns: "String",
+                function: from,
+            },
+        ],
+    }
+}
+
+// What `print` shows, interpolated strings are built with it.
+fn from(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    Ok(Value::String(arguments[0].to_string()))
+}
+
+fn string_argument<'a>(arguments: &'a [Value], function: &str) -> Result<&'a str, String> {
+    match &arguments[0] {
+        Value::String(s) => Ok(s),
+        other => Err(format!(
+            "String.{} expects a string, got {}",
+            function,
+            other.type_name()
+        )),
+    }
+}
+
+fn trim_string(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let s = string_argument(arguments, "trim_string")?;
+    Ok(Value::String(s.trim().to_string()))
+}
+
+fn parse_to_int(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let s = string_argument(arguments, "parse_to_int")?;
+    match s.parse::<i64>() {
+        Ok(n) => Ok(Value::Int(n)),
+        Err(_) => Err(format!("Cannot parse '{}' as an Int", s)),
+    }
+}
+
+fn length(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let s = string_argument(arguments, "length")?;
+    Ok(Value::Int(s.chars().count() as i64))
+}
+
+fn to_upper(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let s = string_argument(arguments, "to_upper")?;
+    Ok(Value::String(s.to_uppercase()))
+}
+
+fn to_lower(_: &Interpreter, arguments: &[Value]) -> Result<Value, String> {
+    let s = string_argument(arguments, "to_lower")?;
+    Ok(Value::String(s.to_lowercase()))
+}
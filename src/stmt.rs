@@ -0,0 +1,169 @@
+use crate::expr::Expr;
+use crate::scanner::{Span, Token};
+use crate::type_expr::TypeExpr;
+
+use std::fmt;
+
+#[derive(Debug, Clone)]
+pub enum Stmt {
+    Expression {
+        expression: Expr,
+        span: Span,
+    },
+    Let {
+        name: Token,
+        mutable: bool,
+        type_annotation: Option<TypeExpr>,
+        initializer: Option<Expr>,
+        doc: Option<String>,
+        span: Span,
+    },
+    Block {
+        statements: Vec<Stmt>,
+        span: Span,
+    },
+    // Fields are `Stmt::Let`s, methods keep their source order.
+    Actor {
+        name: Token,
+        fields: Vec<Stmt>,
+        methods: Vec<Function>,
+        doc: Option<String>,
+        span: Span,
+    },
+    Function(Function),
+    Return {
+        keyword: Token,
+        value: Option<Expr>,
+        span: Span,
+    },
+}
+
+// `fn` runs synchronously on the caller, `be` is an asynchronous behaviour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FunctionKind {
+    Function,
+    Behaviour,
+}
+
+#[derive(Debug, Clone)]
+pub struct Param {
+    pub name: Token,
+    pub type_annotation: Option<TypeExpr>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub kind: FunctionKind,
+    pub name: Token,
+    pub params: Vec<Param>,
+    pub return_type: Option<TypeExpr>,
+    pub body: Vec<Stmt>,
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Stmt {
+    pub fn span(&self) -> Span {
+        match self {
+            Stmt::Expression { span, .. }
+            | Stmt::Let { span, .. }
+            | Stmt::Block { span, .. }
+            | Stmt::Actor { span, .. }
+            | Stmt::Return { span, .. } => *span,
+            Stmt::Function(function) => function.span,
+        }
+    }
+}
+
+impl fmt::Display for Stmt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Stmt::Expression { expression, .. } => write!(f, "{}", expression),
+            Stmt::Let {
+                name,
+                mutable,
+                type_annotation,
+                initializer,
+                ..
+            } => {
+                write!(f, "(let ")?;
+                if *mutable {
+                    write!(f, "mut ")?;
+                }
+                write!(f, "{}", name.lexeme)?;
+                if let Some(annotation) = type_annotation {
+                    write!(f, ": {}", annotation)?;
+                }
+                if let Some(initializer) = initializer {
+                    write!(f, " {}", initializer)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::Block { statements, .. } => {
+                write!(f, "(block")?;
+                for statement in statements {
+                    write!(f, " {}", statement)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::Actor {
+                name,
+                fields,
+                methods,
+                ..
+            } => {
+                write!(f, "(actor {}", name.lexeme)?;
+                for field in fields {
+                    write!(f, " {}", field)?;
+                }
+                for method in methods {
+                    write!(f, " {}", method)?;
+                }
+                write!(f, ")")
+            }
+            Stmt::Function(function) => write!(f, "{}", function),
+            Stmt::Return { value, .. } => match value {
+                Some(value) => write!(f, "(return {})", value),
+                None => write!(f, "(return)"),
+            },
+        }
+    }
+}
+
+impl fmt::Display for FunctionKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FunctionKind::Function => write!(f, "fn"),
+            FunctionKind::Behaviour => write!(f, "be"),
+        }
+    }
+}
+
+impl fmt::Display for Param {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.type_annotation {
+            Some(annotation) => write!(f, "{}: {}", self.name.lexeme, annotation),
+            None => write!(f, "{}", self.name.lexeme),
+        }
+    }
+}
+
+impl fmt::Display for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let params: Vec<String> = self.params.iter().map(|p| p.to_string()).collect();
+        write!(
+            f,
+            "({} {} ({})",
+            self.kind,
+            self.name.lexeme,
+            params.join(" ")
+        )?;
+        if let Some(return_type) = &self.return_type {
+            write!(f, " -> {}", return_type)?;
+        }
+        for statement in &self.body {
+            write!(f, " {}", statement)?;
+        }
+        write!(f, ")")
+    }
+}
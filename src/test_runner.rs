@@ -0,0 +1,185 @@
+use crate::checker::Checker;
+use crate::cst::SyntaxTree;
+use crate::interpreter::Interpreter;
+use crate::{Failure, EX_NOINPUT, EX_USAGE};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const EXPECT: &str = "// expect: ";
+const EXPECT_ERROR: &str = "// expect error: ";
+
+// `loklang test` runs programs and compares what they print with the
+// expectations written in their comments, in order:
+//
+//   print(1 + 2);    // expect: 3
+//   print(1 + "a");  // expect error: Operator '+' cannot be applied to Int and String
+//
+// An error stops the program, so it must be the last expectation. Directories
+// are searched for `.lok` files.
+pub fn run_tests(paths: &[String]) -> Result<(), Failure> {
+    if paths.is_empty() {
+        return Err(Failure::new(
+            EX_USAGE,
+            "Usage: loklang test <file or directory>...\n".to_string(),
+        ));
+    }
+    let mut files = vec![];
+    for path in paths {
+        collect_files(Path::new(path), &mut files).map_err(|err| {
+            Failure::new(EX_NOINPUT, format!("Could not read {}: {}\n", path, err))
+        })?;
+    }
+
+    let mut failed = 0;
+    for file in &files {
+        let problems = match fs::read_to_string(file) {
+            Ok(source) => test_program(&source),
+            Err(err) => vec![format!("could not read the file: {}", err)],
+        };
+        if problems.is_empty() {
+            println!("PASS {}", file.display());
+        } else {
+            failed += 1;
+            println!("FAIL {}", file.display());
+            for problem in problems {
+                println!("    {}", problem);
+            }
+        }
+    }
+    println!("\n{} passed, {} failed", files.len() - failed, failed);
+
+    if failed > 0 {
+        return Err(Failure::new(1, String::new()));
+    }
+    Ok(())
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    if !path.is_dir() {
+        fs::metadata(path)?;
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() || entry.extension().is_some_and(|ext| ext == "lok") {
+            collect_files(&entry, files)?;
+        }
+    }
+    Ok(())
+}
+
+// Runs `source` and describes every way it didn't meet its expectations.
+fn test_program(source: &str) -> Vec<String> {
+    let mut expected = vec![];
+    let mut expected_error = None;
+    for line in source.lines() {
+        if let Some(i) = line.find(EXPECT) {
+            expected.push(line[i + EXPECT.len()..].to_string());
+        } else if let Some(i) = line.find(EXPECT_ERROR) {
+            expected_error = Some(line[i + EXPECT_ERROR.len()..].to_string());
+        }
+    }
+
+    let (output, errors) = run_program(source);
+    let output: Vec<&str> = output.lines().collect();
+
+    let mut problems = vec![];
+    for (i, expected) in expected.iter().enumerate() {
+        match output.get(i) {
+            Some(line) if line == expected => (),
+            Some(line) => problems.push(format!(
+                "line {} of the output: expected '{}', got '{}'",
+                i + 1,
+                expected,
+                line
+            )),
+            None => problems.push(format!("missing output: expected '{}'", expected)),
+        }
+    }
+    for line in output.iter().skip(expected.len()) {
+        problems.push(format!("unexpected output '{}'", line));
+    }
+    let mut errors = errors.into_iter();
+    match (expected_error, errors.next()) {
+        (Some(expected), Some(error)) if expected == error => (),
+        (Some(expected), Some(error)) => {
+            problems.push(format!("expected error '{}', got '{}'", expected, error))
+        }
+        (Some(expected), None) => problems.push(format!("expected error '{}'", expected)),
+        (None, Some(error)) => problems.push(format!("error: {}", error)),
+        (None, None) => (),
+    }
+    for error in errors {
+        problems.push(format!("unexpected error '{}'", error));
+    }
+    problems
+}
+
+// What the program printed, and the messages of the errors that stopped it.
+// Only runtime errors can come after the first, from failed behaviours.
+fn run_program(source: &str) -> (String, Vec<String>) {
+    let tree = match SyntaxTree::parse(source) {
+        Ok(tree) => tree,
+        Err(errors) => return (String::new(), vec![errors[0].message.clone()]),
+    };
+    let statements = match tree.ast() {
+        Ok(statements) => statements,
+        Err(errors) => return (String::new(), vec![errors[0].message.clone()]),
+    };
+    if let Err(errors) = Checker::new().check(&statements) {
+        return (String::new(), vec![errors[0].message.clone()]);
+    }
+
+    let output = Arc::new(Mutex::new(Vec::<u8>::new()));
+    let result = Interpreter::with_output(output.clone()).interpret(&statements);
+    let printed = String::from_utf8_lossy(&output.lock().unwrap()).to_string();
+    let errors = result.err().unwrap_or_default();
+    (printed, errors.into_iter().map(|err| err.message).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_output_with_expectations() {
+        assert!(test_program("print(1 + 2); // expect: 3\nprint(\"a\"); // expect: a").is_empty());
+        assert_eq!(
+            test_program("print(1); // expect: 2\n// expect: 3\nprint(4);"),
+            vec![
+                "line 1 of the output: expected '2', got '1'",
+                "line 2 of the output: expected '3', got '4'"
+            ]
+        );
+        assert_eq!(
+            test_program("print(1); // expect: 1\n// expect: 2\n"),
+            vec!["missing output: expected '2'"]
+        );
+        assert_eq!(
+            test_program("print(1); print(2); // expect: 1"),
+            vec!["unexpected output '2'"]
+        );
+    }
+
+    #[test]
+    fn compares_errors() {
+        assert!(test_program(
+            "print(1); // expect: 1\nlet a = 1 / 0; // expect error: Division by zero"
+        )
+        .is_empty());
+        assert_eq!(
+            test_program("let a = ;"),
+            vec!["error: Expected expression"]
+        );
+        assert_eq!(
+            test_program("let a = 1; // expect error: Division by zero"),
+            vec!["expected error 'Division by zero'"]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use crate::capability::Capability;
+use crate::scanner::{Span, Token};
+
+use std::fmt;
+
+// A type as written in the source, on parameters and `let` bindings:
+//
+//   Int               named type
+//   String iso        named type with a reference capability
+//   (Int | String)    union, the parentheses are optional
+//   (Int, String)     tuple, `()` is the empty tuple
+#[derive(Debug, Clone)]
+pub enum TypeExpr {
+    Named {
+        name: Token,
+        capability: Option<Capability>,
+        span: Span,
+    },
+    Union {
+        types: Vec<TypeExpr>,
+        span: Span,
+    },
+    Tuple {
+        types: Vec<TypeExpr>,
+        span: Span,
+    },
+}
+
+impl TypeExpr {
+    pub fn span(&self) -> Span {
+        match self {
+            TypeExpr::Named { span, .. }
+            | TypeExpr::Union { span, .. }
+            | TypeExpr::Tuple { span, .. } => *span,
+        }
+    }
+}
+
+// Spans are ignored, two annotations are equal if they spell the same type.
+impl PartialEq for TypeExpr {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                TypeExpr::Named {
+                    name: a,
+                    capability: a_capability,
+                    ..
+                },
+                TypeExpr::Named {
+                    name: b,
+                    capability: b_capability,
+                    ..
+                },
+            ) => a.lexeme == b.lexeme && a_capability == b_capability,
+            (TypeExpr::Union { types: a, .. }, TypeExpr::Union { types: b, .. })
+            | (TypeExpr::Tuple { types: a, .. }, TypeExpr::Tuple { types: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for TypeExpr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TypeExpr::Named {
+                name,
+                capability: Some(capability),
+                ..
+            } => write!(f, "{} {}", name.lexeme, capability),
+            TypeExpr::Named { name, .. } => write!(f, "{}", name.lexeme),
+            TypeExpr::Union { types, .. } => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                write!(f, "({})", types.join(" | "))
+            }
+            TypeExpr::Tuple { types, .. } => {
+                let types: Vec<String> = types.iter().map(|t| t.to_string()).collect();
+                if types.len() == 1 {
+                    write!(f, "({},)", types[0])
+                } else {
+                    write!(f, "({})", types.join(", "))
+                }
+            }
+        }
+    }
+}